@@ -59,7 +59,7 @@ fn motion(
     yaks::batch(
         &mut context.query(no_acceleration),
         spawned.batch_size_no_acceleration(),
-        |_entity, (mut pos, vel)| {
+        |_entity, (pos, vel)| {
             pos.0 += vel.0;
             pos.1 += vel.1;
         },
@@ -68,7 +68,7 @@ fn motion(
     yaks::batch(
         &mut context.query(with_acceleration),
         spawned.batch_size_with_acceleration(),
-        |_entity, (mut pos, mut vel, acc)| {
+        |_entity, (pos, vel, acc)| {
             vel.0 += acc.0;
             vel.1 += acc.1;
             pos.0 += vel.0;
@@ -105,7 +105,7 @@ fn color(
     yaks::batch(
         &mut context.query(query),
         spawned.batch_size_all(),
-        |_entity, (pos, vel, mut col)| {
+        |_entity, (pos, vel, col)| {
             col.0 = pos.0.abs() / 1000.0;
             col.1 = vel.1.abs() / 100.0;
             col.2 = blue;
@@ -255,5 +255,5 @@ fn main() {
         },
     );
     find_average_color.run(world, (&mut average_color, &spawned));
-    assert!((average_color.3 - 0.5).abs() < std::f32::EPSILON);
+    assert!((average_color.3 - 0.5).abs() < f32::EPSILON);
 }
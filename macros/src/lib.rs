@@ -0,0 +1,196 @@
+//! Procedural macros for [`yaks`](https://crates.io/crates/yaks);
+//! use the `macros` feature of `yaks` instead of depending on this crate directly.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, punctuated::Punctuated, token::Comma, Data, DeriveInput, Error,
+    Field, Fields, GenericParam, Lifetime, LifetimeDef,
+};
+
+/// Implements `yaks::ResourceBundle` for a struct with named fields;
+/// see the trait's documentation.
+#[proc_macro_derive(ResourceBundle)]
+pub fn derive_resource_bundle(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match resource_bundle(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Implements the resource fetching trait of `yaks` for a struct with named fields, allowing it
+/// to be used as a system's resources argument; see `yaks::ExecutorBuilder::system()`.
+#[proc_macro_derive(SystemParams)]
+pub fn derive_system_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match system_params(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn named_fields<'a>(
+    input: &'a DeriveInput,
+    derive: &str,
+) -> Result<&'a Punctuated<Field, Comma>, Error> {
+    if let Data::Struct(data) = &input.data {
+        if let Fields::Named(fields) = &data.fields {
+            return Ok(&fields.named);
+        }
+    }
+    Err(Error::new_spanned(
+        &input.ident,
+        format!(
+            "`{}` can only be derived for structs with named fields",
+            derive
+        ),
+    ))
+}
+
+fn resource_bundle(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let ident = &input.ident;
+    let fields = named_fields(&input, "ResourceBundle")?;
+    if fields.len() > 16 {
+        return Err(Error::new_spanned(
+            ident,
+            "`ResourceBundle` can only be derived for structs with up to 16 fields",
+        ));
+    }
+    let names: Vec<_> = fields
+        .iter()
+        .map(|field| field.ident.as_ref().expect("fields should be named"))
+        .collect();
+    let types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let labels = names.iter().map(|name| format!("{}::{}", ident, name));
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let mut source_generics = input.generics.clone();
+    source_generics.params.insert(
+        0,
+        GenericParam::Lifetime(LifetimeDef::new(Lifetime::new(
+            "'__yaks",
+            Span::call_site(),
+        ))),
+    );
+    let (source_impl_generics, _, _) = source_generics.split_for_impl();
+    let sources = names
+        .iter()
+        .zip(&types)
+        .enumerate()
+        .map(|(index, (name, ty))| {
+            quote! {
+                unsafe impl #source_impl_generics ::yaks::ResourceSource<#ty, [(); #index]>
+                    for &'__yaks mut #ident #type_generics #where_clause
+                {
+                    const MUTABLE: bool = true;
+
+                    fn pointer(&mut self) -> ::std::ptr::NonNull<#ty> {
+                        ::std::ptr::NonNull::from(&mut self.#name)
+                    }
+                }
+            }
+        });
+    Ok(quote! {
+        impl #impl_generics ::yaks::ResourceBundle for #ident #type_generics #where_clause {
+            type Tuple = (#(#types,)*);
+            const LABELS: &'static [&'static str] = &[#(#labels,)*];
+
+            fn wrap(
+                &mut self,
+                borrows: &mut <Self::Tuple as ::yaks::__private::ResourceTuple>::BorrowTuple,
+            ) -> <Self::Tuple as ::yaks::__private::ResourceTuple>::Wrapped {
+                ::yaks::__private::ResourceWrap::wrap(&mut (#(&mut self.#names,)*), borrows)
+            }
+        }
+
+        #(#sources)*
+    })
+}
+
+fn system_params(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let ident = &input.ident;
+    let fields = named_fields(&input, "SystemParams")?;
+    let mut generics = input.generics.clone();
+    let lifetime = match input.generics.lifetimes().count() {
+        0 => {
+            let lifetime = Lifetime::new("'__yaks", Span::call_site());
+            generics.params.insert(
+                0,
+                GenericParam::Lifetime(LifetimeDef::new(lifetime.clone())),
+            );
+            lifetime
+        }
+        1 => input
+            .generics
+            .lifetimes()
+            .next()
+            .expect("there should be a lifetime")
+            .lifetime
+            .clone(),
+        _ => {
+            return Err(Error::new_spanned(
+                &input.generics,
+                "`SystemParams` can only be derived for structs with at most one lifetime",
+            ))
+        }
+    };
+    let names: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+    let types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let markers: Vec<_> = (0..fields.len())
+        .map(|index| format_ident!("__YaksM{}", index))
+        .collect();
+    generics.params.push(parse_quote!(__YaksT));
+    for marker in &markers {
+        generics.params.push(parse_quote!(#marker));
+    }
+    let where_clause = generics.make_where_clause();
+    for (field_type, marker) in types.iter().zip(&markers) {
+        where_clause.predicates.push(parse_quote!(
+            #field_type: ::yaks::__private::FetchField<#lifetime, __YaksT, #marker>
+        ));
+    }
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, type_generics, _) = input.generics.split_for_impl();
+    let fetches: Vec<_> = types
+        .iter()
+        .zip(&markers)
+        .map(|(field_type, marker)| {
+            quote!(<#field_type as ::yaks::__private::FetchField<#lifetime, __YaksT, #marker>>)
+        })
+        .collect();
+    Ok(quote! {
+        impl #impl_generics ::yaks::__private::Fetch<#lifetime, __YaksT, (#(#markers,)*)>
+            for #ident #type_generics #where_clause
+        {
+            fn fetch(resources: &#lifetime __YaksT) -> Self {
+                Self {
+                    #(#names: #fetches::fetch(resources),)*
+                }
+            }
+
+            unsafe fn release(resources: &#lifetime __YaksT) {
+                #(#fetches::release(resources);)*
+            }
+
+            fn set_resource_bits(resource_set: &mut ::yaks::__private::BorrowSet) {
+                #(#fetches::set_resource_bits(resource_set);)*
+            }
+
+            fn insert_component_types(
+                component_type_set: &mut ::yaks::__private::BorrowTypeSet,
+            ) {
+                #(#fetches::insert_component_types(component_type_set);)*
+            }
+
+            fn set_archetype_bits(
+                world: &::yaks::__private::World,
+                archetype_set: &mut ::yaks::__private::ArchetypeSet,
+            ) {
+                #(#fetches::set_archetype_bits(world, archetype_set);)*
+            }
+        }
+    })
+}
@@ -1,12 +1,19 @@
 use fixedbitset::FixedBitSet;
-use hecs::{Access, Query, World};
-use std::{any::TypeId, collections::HashSet};
+use hecs::{Access, Archetype, Query, World};
+use std::{
+    any::{type_name, TypeId},
+    collections::HashSet,
+    sync::Arc,
+};
 
 pub type TypeSet = HashSet<TypeId>;
 
+#[derive(Clone)]
 pub struct BorrowTypeSet {
     pub immutable: TypeSet,
     pub mutable: TypeSet,
+    /// Name of the first component type that a single query borrows incompatibly.
+    pub conflict: Option<&'static str>,
 }
 
 impl BorrowTypeSet {
@@ -16,9 +23,40 @@ impl BorrowTypeSet {
         Self {
             immutable: TypeSet::new(),
             mutable: TypeSet::new(),
+            conflict: None,
         }
     }
 
+    pub fn insert_immutable<C0: 'static>(&mut self) {
+        let id = TypeId::of::<C0>();
+        if self.mutable.contains(&id) {
+            self.conflict.get_or_insert(type_name::<C0>());
+        }
+        self.immutable.insert(id);
+    }
+
+    pub fn insert_mutable<C0: 'static>(&mut self) {
+        let id = TypeId::of::<C0>();
+        if self.mutable.contains(&id) || self.immutable.contains(&id) {
+            self.conflict.get_or_insert(type_name::<C0>());
+        }
+        self.mutable.insert(id);
+    }
+
+    /// Adds borrows of another set without checking them against borrows of this one,
+    /// keeping the first known conflict.
+    pub fn extend(&mut self, other: &BorrowTypeSet) {
+        self.immutable.extend(&other.immutable);
+        self.mutable.extend(&other.mutable);
+        if self.conflict.is_none() {
+            self.conflict = other.conflict;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.immutable.is_empty() && self.mutable.is_empty()
+    }
+
     pub fn condense(self, all_types: &[TypeId]) -> BorrowSet {
         let mut set = BorrowSet::with_capacity(all_types.len());
         all_types.iter().enumerate().for_each(|(index, element)| {
@@ -33,9 +71,24 @@ impl BorrowTypeSet {
     }
 }
 
+/// Identifies a resource in the resource sets of an executor's systems.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ResourceKey {
+    /// Position of the resource in the executor's tuple of resources.
+    Index(usize),
+    /// Type of a resource in a container keyed by type, such as `ResourceMap`; replaced with
+    /// an index when the executor is built, see `BorrowSet::condense_types()`.
+    Type(TypeId),
+}
+
 pub struct BorrowSet {
     pub immutable: FixedBitSet,
     pub mutable: FixedBitSet,
+    /// Borrows of resources identified by type, not yet assigned an index.
+    pub types: BorrowTypeSet,
+    /// Name of the first resource type that is borrowed incompatibly with an earlier borrow
+    /// recorded with `insert_immutable()` or `insert_mutable()`.
+    pub conflict: Option<&'static str>,
 }
 
 impl BorrowSet {
@@ -43,6 +96,61 @@ impl BorrowSet {
         Self {
             immutable: FixedBitSet::with_capacity(bits),
             mutable: FixedBitSet::with_capacity(bits),
+            types: BorrowTypeSet::new(),
+            conflict: None,
+        }
+    }
+
+    pub fn insert_immutable<R0>(&mut self, key: ResourceKey) {
+        let conflicts = match key {
+            ResourceKey::Index(index) => {
+                self.immutable.grow(index + 1);
+                self.immutable.put(index);
+                self.mutable.contains(index)
+            }
+            ResourceKey::Type(id) => {
+                self.types.immutable.insert(id);
+                self.types.mutable.contains(&id)
+            }
+        };
+        if conflicts {
+            self.conflict.get_or_insert(type_name::<R0>());
+        }
+    }
+
+    pub fn insert_mutable<R0>(&mut self, key: ResourceKey) {
+        let conflicts = match key {
+            ResourceKey::Index(index) => {
+                self.mutable.grow(index + 1);
+                self.mutable.put(index) || self.immutable.contains(index)
+            }
+            ResourceKey::Type(id) => {
+                !self.types.mutable.insert(id) || self.types.immutable.contains(&id)
+            }
+        };
+        if conflicts {
+            self.conflict.get_or_insert(type_name::<R0>());
+        }
+    }
+
+    pub fn extend(&mut self, other: &BorrowSet) {
+        self.immutable.union_with(&other.immutable);
+        self.mutable.union_with(&other.mutable);
+        self.types.extend(&other.types);
+    }
+
+    /// Replaces borrows of resources identified by type with bits of their positions
+    /// in `all_types`, offset by `offset`.
+    pub fn condense_types(&mut self, all_types: &[TypeId], offset: usize) {
+        let types = std::mem::replace(&mut self.types, BorrowTypeSet::new());
+        let condensed = types.condense(all_types);
+        for index in condensed.immutable.ones() {
+            self.immutable.grow(offset + index + 1);
+            self.immutable.insert(offset + index);
+        }
+        for index in condensed.mutable.ones() {
+            self.mutable.grow(offset + index + 1);
+            self.mutable.insert(offset + index);
         }
     }
 
@@ -53,36 +161,118 @@ impl BorrowSet {
     }
 }
 
-#[derive(Default)]
+/// Accesses of systems to components of archetypes; the bit of the component with index `c`
+/// in the archetype with index `a` is `a * components.len() + c`.
 pub struct ArchetypeSet {
     pub immutable: FixedBitSet,
     pub mutable: FixedBitSet,
+    /// Component types that bits are recorded for, shared by all systems of an executor.
+    components: Arc<[TypeId]>,
+    /// Examined archetypes matched by any of the recorded queries, including the ones
+    /// they access no components of (e.g. with `Option` queries).
+    matched: FixedBitSet,
+    /// Amount of archetypes the bits have been set for; since archetypes are never removed
+    /// from a world, only the ones after these need to be examined when it changes.
+    written: usize,
 }
 
 impl ArchetypeSet {
+    pub fn new(components: Arc<[TypeId]>) -> Self {
+        Self {
+            immutable: FixedBitSet::default(),
+            mutable: FixedBitSet::default(),
+            components,
+            matched: FixedBitSet::default(),
+            written: 0,
+        }
+    }
+
     pub fn is_compatible(&self, other: &ArchetypeSet) -> bool {
         self.mutable.is_disjoint(&other.mutable)
             && self.mutable.is_disjoint(&other.immutable)
             && self.immutable.is_disjoint(&other.mutable)
     }
 
-    pub fn set_bits_for_query<Q>(&mut self, world: &World)
-    where
-        Q: Query,
-    {
+    /// Whether any of the examined archetypes matched by the recorded queries contain entities.
+    pub fn has_entities(&self, world: &World) -> bool {
+        world
+            .archetypes()
+            .enumerate()
+            .any(|(index, archetype)| self.matched.contains(index) && !archetype.is_empty())
+    }
+
+    pub fn clear(&mut self) {
         self.immutable.clear();
         self.mutable.clear();
-        let iterator = world.archetypes();
-        let bits = iterator.len();
+        self.matched.clear();
+        self.written = 0;
+    }
+
+    /// Marks all archetypes of the world as examined, so that following calls to
+    /// `set_bits_for_query()` and `set_bits_for_types()` only examine newer ones.
+    pub fn mark_written(&mut self, world: &World) {
+        self.written = world.archetypes().len();
+    }
+
+    /// Sets bits of components of given set that the archetype with given index contains.
+    fn set_bits_for_archetype(
+        &mut self,
+        index: usize,
+        archetype: &Archetype,
+        types: &BorrowTypeSet,
+    ) {
+        let stride = self.components.len();
+        for (component, id) in self.components.iter().enumerate() {
+            if !archetype.has_dynamic(*id) {
+                continue;
+            }
+            if types.mutable.contains(id) {
+                self.mutable.insert(index * stride + component);
+            } else if types.immutable.contains(id) {
+                self.immutable.insert(index * stride + component);
+            }
+        }
+    }
+
+    fn grow(&mut self, world: &World) {
+        let bits = world.archetypes().len() * self.components.len();
         self.immutable.grow(bits);
         self.mutable.grow(bits);
-        iterator
-            .enumerate()
-            .filter_map(|(index, archetype)| archetype.access::<Q>().map(|access| (index, access)))
-            .for_each(|(archetype, access)| match access {
-                Access::Read => self.immutable.set(archetype, true),
-                Access::Write => self.mutable.set(archetype, true),
-                Access::Iterate => (),
-            });
+        self.matched.grow(world.archetypes().len());
+    }
+
+    /// Sets bits of components the query would access, with given component types,
+    /// in archetypes it matches that haven't been examined yet, and marks them as matched,
+    /// keeping bits that are already set.
+    pub fn set_bits_for_query<Q>(&mut self, world: &World, types: &BorrowTypeSet)
+    where
+        Q: Query,
+    {
+        self.grow(world);
+        for (index, archetype) in world.archetypes().enumerate().skip(self.written) {
+            match archetype.access::<Q>() {
+                Some(Access::Read) | Some(Access::Write) => {
+                    self.set_bits_for_archetype(index, archetype, types);
+                }
+                Some(Access::Iterate) => (),
+                None => continue,
+            }
+            self.matched.insert(index);
+        }
+    }
+
+    /// Sets bits of given component types in archetypes that contain them and haven't been
+    /// examined yet, and marks those archetypes as matched, keeping bits that are already set.
+    pub fn set_bits_for_types(&mut self, world: &World, types: &BorrowTypeSet) {
+        self.grow(world);
+        for (index, archetype) in world.archetypes().enumerate().skip(self.written) {
+            if archetype
+                .component_types()
+                .any(|id| types.immutable.contains(&id) || types.mutable.contains(&id))
+            {
+                self.matched.insert(index);
+            }
+            self.set_bits_for_archetype(index, archetype, types);
+        }
     }
 }
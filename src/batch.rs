@@ -1,4 +1,7 @@
-use hecs::{Entity, Fetch, Query, QueryBorrow};
+use hecs::{Entity, Fetch, Query, QueryBorrow, QueryItem};
+use std::collections::{HashMap, HashSet};
+
+use crate::{QueryMarker, SystemContext};
 
 #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
 /// Distributes over a `rayon` thread pool the work of applying a function to items in a query.
@@ -128,3 +131,252 @@ pub fn batch<'query, 'world, Q, F>(
             .for_each(|(entity, components)| for_each(entity, components));
     }
 }
+
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+/// Same as [`yaks::batch()`](fn.batch.html), but with a value created by `init` that
+/// is given mutably to `for_each` along with the items; see
+/// [`rayon::iter::ParallelIterator::for_each_init()`][fei].
+///
+/// The value is reused across batches processed by the same `rayon` job, which makes it
+/// suitable for scratch buffers, random number generators, and other state that is
+/// expensive to create per item. How many values are created is up to `rayon`, and
+/// no assumptions should be made about it beyond there being at least one per thread
+/// that processes any batches.
+///
+/// If the default `parallel` feature is disabled `init` is called exactly once.
+///
+/// [fei]: ../rayon/iter/trait.ParallelIterator.html#method.for_each_init
+///
+/// # Example
+/// ```rust
+/// # struct Pos(f32);
+/// # struct Path(Vec<f32>);
+/// # let world = hecs::World::new();
+/// # let num_entities = 64;
+/// yaks::batch_with_init(
+///     &mut world.query::<(&Pos, &mut Path)>(),
+///     num_entities / 16,
+///     || Vec::with_capacity(64),
+///     |scratch: &mut Vec<f32>, _entity, (pos, path)| {
+///         scratch.clear();
+///         scratch.push(pos.0);
+///         path.0.extend(scratch.iter());
+///     },
+/// );
+/// ```
+pub fn batch_with_init<'query, 'world, Q, I, T, F>(
+    query_borrow: &'query mut QueryBorrow<'world, Q>,
+    batch_size: u32,
+    init: I,
+    for_each: F,
+) where
+    Q: Query + Send + Sync + 'query,
+    I: Fn() -> T + Send + Sync,
+    F: Fn(&mut T, Entity, <<Q as Query>::Fetch as Fetch<'query>>::Item) + Send + Sync,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::{ParallelBridge, ParallelIterator};
+        query_borrow
+            .iter_batched(batch_size)
+            .par_bridge()
+            .for_each_init(init, |value, batch| {
+                batch.for_each(|(entity, components)| for_each(value, entity, components))
+            });
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        let mut value = init();
+        query_borrow
+            .iter()
+            .for_each(|(entity, components)| for_each(&mut value, entity, components));
+    }
+}
+
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+/// Same as [`yaks::batch()`](fn.batch.html), but with a fallible function; stops
+/// distributing the work as soon as any call returns an error, and returns that error;
+/// see [`rayon::iter::ParallelIterator::try_for_each()`][tfe].
+///
+/// Only batches that haven't been started yet when the error occurs are skipped: any other
+/// batches that are being processed at that moment are processed to completion, unless
+/// they fail themselves. If several calls fail, which of the errors is returned is unspecified.
+///
+/// If the default `parallel` feature is disabled the functionality is identical
+/// to `query_borrow.iter().try_for_each(for_each)`.
+///
+/// [tfe]: ../rayon/iter/trait.ParallelIterator.html#method.try_for_each
+///
+/// # Example
+/// ```rust
+/// # struct Health(i32);
+/// let mut world = hecs::World::new();
+/// world.spawn_batch((0..64).map(|index| (Health(100 - index),)));
+/// let invalid = world.spawn((Health(-1),));
+/// let result = yaks::try_batch(
+///     &mut world.query::<&Health>(),
+///     16,
+///     |entity, health| {
+///         if health.0 < 0 {
+///             Err(entity)
+///         } else {
+///             Ok(())
+///         }
+///     },
+/// );
+/// assert_eq!(result, Err(invalid));
+/// ```
+pub fn try_batch<'query, 'world, Q, E, F>(
+    query_borrow: &'query mut QueryBorrow<'world, Q>,
+    batch_size: u32,
+    for_each: F,
+) -> Result<(), E>
+where
+    Q: Query + Send + Sync + 'query,
+    E: Send,
+    F: Fn(Entity, <<Q as Query>::Fetch as Fetch<'query>>::Item) -> Result<(), E> + Send + Sync,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::{ParallelBridge, ParallelIterator};
+        query_borrow
+            .iter_batched(batch_size)
+            .par_bridge()
+            .try_for_each(|mut batch| {
+                batch.try_for_each(|(entity, components)| for_each(entity, components))
+            })
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        query_borrow
+            .iter()
+            .try_for_each(|(entity, components)| for_each(entity, components))
+    }
+}
+
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+/// Distributes over a `rayon` thread pool the work of applying a function to query items
+/// of specific entities; see [`yaks::batch()`](fn.batch.html) and
+/// [`SystemContext::query_one()`](struct.SystemContext.html#method.query_one).
+///
+/// Entities that don't exist or don't match the query are not passed to the function,
+/// and are instead returned, in the order they first appear in `entities`. Each entity is
+/// processed or returned at most once, even if it appears in `entities` several times.
+///
+/// Locating the entities requires looking through IDs of all entities in archetypes that
+/// match the query, so this is best suited for when the function is expensive enough
+/// to warrant spreading it across threads.
+///
+/// If the default `parallel` feature is disabled the entities are processed in a single thread.
+///
+/// # Example
+/// ```rust
+/// # use yaks::{QueryMarker, SystemContext};
+/// # struct Pos(f32);
+/// # struct Vel(f32);
+/// fn some_system(
+///     context: SystemContext,
+///     selected: &Vec<hecs::Entity>,
+///     query: QueryMarker<(&mut Pos, &Vel)>,
+/// ) {
+///     let missing = yaks::batch_entities(
+///         &context,
+///         query,
+///         selected,
+///         16,
+///         |_entity, (pos, vel)| {
+///             pos.0 += vel.0;
+///         },
+///     );
+///     for entity in missing {
+///         println!("{:?} no longer has position and velocity", entity);
+///     }
+/// }
+/// ```
+pub fn batch_entities<Q, F>(
+    context: &SystemContext,
+    query: QueryMarker<Q>,
+    entities: &[Entity],
+    batch_size: u32,
+    for_each: F,
+) -> Vec<Entity>
+where
+    Q: Query + Send + Sync,
+    F: for<'q> Fn(Entity, QueryItem<'q, Q>) + Send + Sync,
+{
+    let world = context.world;
+    // Live requested entities, keyed by ID; this also discards duplicates.
+    let mut requested: HashMap<u32, Entity> = entities
+        .iter()
+        .filter(|entity| world.contains(**entity))
+        .map(|entity| (entity.id(), *entity))
+        .collect();
+    // Find rows of requested entities, grouped by archetype.
+    let mut located = Vec::new();
+    for (archetype_index, archetype) in world.archetypes().enumerate() {
+        if requested.is_empty() {
+            break;
+        }
+        if archetype.access::<Q>().is_none() {
+            continue;
+        }
+        let rows: Vec<_> = archetype
+            .ids()
+            .iter()
+            .enumerate()
+            .filter_map(|(row, id)| requested.remove(id).map(|entity| (row, entity)))
+            .collect();
+        if !rows.is_empty() {
+            located.push((archetype_index, rows));
+        }
+    }
+    // Anything still requested exists, but doesn't match the query.
+    let mut reported = HashSet::new();
+    let missing = entities
+        .iter()
+        .filter(|entity| !world.contains(**entity) || requested.contains_key(&entity.id()))
+        .filter(|entity| reported.insert(**entity))
+        .copied()
+        .collect();
+    // Calling `iter()` is what acquires the query's borrows; they are held until
+    // the end of this function, after all of the entities are processed.
+    let mut query_borrow = context.query(query);
+    let _iter = query_borrow.iter();
+    let process = |archetype_index: usize, rows: &[(usize, Entity)]| {
+        let archetype = world
+            .archetypes()
+            .nth(archetype_index)
+            .expect("archetypes should not change while the query is borrowed");
+        let fetch = <Q::Fetch as Fetch>::new(archetype)
+            .expect("located entities should always match the query");
+        for (row, entity) in rows {
+            // SAFETY: `row` is an index into `archetype.ids()`, so it's in bounds, and
+            // archetypes can't change while the world is borrowed. The components are
+            // borrowed by the query until the end of the function, so no other accesses
+            // can conflict. Each row is located at most once, so the items of mutable
+            // fetches don't alias.
+            for_each(*entity, unsafe { fetch.get(*row) });
+        }
+    };
+    #[cfg(feature = "parallel")]
+    {
+        // Fetches are not `Sync`, so each batch creates its own; batches never span archetypes.
+        use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+        located
+            .iter()
+            .flat_map(|(archetype_index, rows)| {
+                rows.chunks(batch_size.max(1) as usize)
+                    .map(move |batch| (*archetype_index, batch))
+            })
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .for_each(|(archetype_index, batch)| process(archetype_index, batch));
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        for (archetype_index, rows) in &located {
+            process(*archetype_index, rows);
+        }
+    }
+    missing
+}
@@ -1,13 +1,16 @@
-use std::{collections::HashMap, fmt::Debug, hash::Hash};
-
-#[cfg(feature = "parallel")]
 use hecs::World;
+use std::{any::type_name, collections::HashMap, fmt::Debug, hash::Hash, time::Duration};
 
-use super::SystemClosure;
-use crate::{Executor, Fetch, QueryBundle, ResourceTuple, SystemContext, SystemId};
+use super::{ArchetypeWriter, Plugin, SystemClosure};
+use crate::{
+    resource::{Contains, NestedResources},
+    system_context::RunningSystem,
+    ArchetypeSet, BorrowSet, BorrowTypeSet, Executor, Fetch, QueryBundle, ResourceKey,
+    ResourceTuple, SchedulingPolicy, SystemContext, SystemId,
+};
 
 #[cfg(feature = "parallel")]
-use crate::{ArchetypeSet, BorrowSet, BorrowTypeSet, TypeSet};
+use crate::TypeSet;
 
 /// Container for parsed systems and their metadata;
 /// destructured in concrete executors' build functions.
@@ -16,13 +19,22 @@ where
     Resources: ResourceTuple + 'closure,
 {
     pub closure: Box<SystemClosure<'closure, Resources::Wrapped>>,
+    pub name: &'static str,
     pub dependencies: Vec<SystemId>,
-    #[cfg(feature = "parallel")]
     pub resource_set: BorrowSet,
-    #[cfg(feature = "parallel")]
     pub component_type_set: BorrowTypeSet,
+    pub archetype_writer: Box<ArchetypeWriter>,
+    /// Address of the archetype writer if it's a function shared by all systems
+    /// with the same queries and resources, which can then share archetype sets.
     #[cfg(feature = "parallel")]
-    pub archetype_writer: Box<dyn Fn(&World, &mut ArchetypeSet) + Send>,
+    pub archetype_writer_key: Option<usize>,
+    /// Whether the system is skipped when none of its queries match any archetypes.
+    pub skip_if_unmatched: bool,
+    #[cfg(feature = "parallel")]
+    pub priority: i32,
+    /// Expected duration of the system, used until it's measured.
+    #[cfg(feature = "parallel")]
+    pub cost: Option<Duration>,
 }
 
 /// A builder for [`Executor`](struct.Executor.html) (and the only way of creating one).
@@ -34,6 +46,13 @@ where
     pub(crate) handles: HashMap<Handle, SystemId>,
     #[cfg(feature = "parallel")]
     pub(crate) all_component_types: TypeSet,
+    /// Policy set with `scheduling_policy()`, if any; the default is `DependantsLength`.
+    #[cfg(feature = "parallel")]
+    pub(crate) scheduling_policy: Option<Box<dyn SchedulingPolicy + 'closures>>,
+    #[cfg(feature = "parallel")]
+    pub(crate) max_concurrent_systems: usize,
+    /// Keys and names of resources declared with `read_only()`.
+    pub(crate) read_only: Vec<(ResourceKey, &'static str)>,
 }
 
 impl<'closures, Resources, Handle> ExecutorBuilder<'closures, Resources, Handle>
@@ -50,8 +69,10 @@ where
         ResourceRefs: Fetch<'a, Resources::Wrapped, Markers> + 'a,
         Queries: QueryBundle,
     {
+        let name = type_name::<Closure>();
         let closure = Box::new(
             move |context: SystemContext<'a>, resources: &'a Resources::Wrapped| {
+                let _running = context.system_name.map(RunningSystem::enter);
                 let fetched = ResourceRefs::fetch(resources);
                 closure(context, fetched, Queries::markers());
                 unsafe { ResourceRefs::release(resources) };
@@ -63,28 +84,183 @@ where
                 Box<dyn FnMut(SystemContext, &Resources::Wrapped) + Send + Sync + 'closures>,
             >(closure)
         };
-        #[cfg(feature = "parallel")]
-        {
-            let mut resource_set = BorrowSet::with_capacity(Resources::LENGTH);
-            ResourceRefs::set_resource_bits(&mut resource_set);
-            let mut component_type_set = BorrowTypeSet::new();
-            Queries::insert_component_types(&mut component_type_set);
-            let archetype_writer = Box::new(|world: &World, archetype_set: &mut ArchetypeSet| {
-                Queries::set_archetype_bits(world, archetype_set)
-            });
-            System {
-                closure,
-                dependencies: vec![],
-                resource_set,
-                component_type_set,
-                archetype_writer,
-            }
+        let mut resource_set = BorrowSet::with_capacity(Resources::LENGTH);
+        ResourceRefs::set_resource_bits(&mut resource_set);
+        if let Some(resource) = resource_set.conflict {
+            panic!(
+                "system {} borrows resource {} mutably more than once, \
+                or both mutably and immutably",
+                name, resource
+            );
+        }
+        let mut component_type_set = BorrowTypeSet::new();
+        Queries::insert_component_types(&mut component_type_set);
+        ResourceRefs::insert_component_types(&mut component_type_set);
+        if let Some(component) = component_type_set.conflict {
+            panic!(
+                "system {} makes a query that borrows component {} mutably more than once, \
+                or both mutably and immutably",
+                name, component
+            );
+        }
+        let archetype_writer: fn(&World, &mut ArchetypeSet) =
+            write_archetype_bits::<Resources::Wrapped, ResourceRefs, Queries, Markers>;
+        System {
+            closure,
+            name,
+            dependencies: vec![],
+            resource_set,
+            component_type_set,
+            archetype_writer: Box::new(archetype_writer),
+            #[cfg(feature = "parallel")]
+            archetype_writer_key: Some(archetype_writer as usize),
+            skip_if_unmatched: false,
+            #[cfg(feature = "parallel")]
+            priority: 0,
+            #[cfg(feature = "parallel")]
+            cost: None,
         }
-        #[cfg(not(feature = "parallel"))]
+    }
+
+    fn box_executor<Nested, Markers>(
+        mut executor: Executor<'closures, Nested>,
+    ) -> System<'closures, Resources>
+    where
+        Nested: NestedResources<Resources::Wrapped, Markers> + 'closures,
+        Executor<'closures, Nested>: Send + Sync,
+    {
+        let mut resource_set = BorrowSet::with_capacity(Resources::LENGTH);
+        Nested::set_resource_bits(&executor.resource_set, &mut resource_set);
+        let component_type_set = executor.component_type_set.clone();
+        let name = type_name::<Executor<'closures, Nested>>();
+        let closure = Box::new(
+            move |context: SystemContext, resources: &Resources::Wrapped| {
+                let _running = context.system_name.map(RunningSystem::enter);
+                Nested::run_nested(&mut executor, context.world, resources);
+            },
+        );
+        let types = component_type_set.clone();
+        let archetype_writer = Box::new(move |world: &World, archetype_set: &mut ArchetypeSet| {
+            archetype_set.set_bits_for_types(world, &types);
+        });
         System {
             closure,
+            name,
             dependencies: vec![],
+            resource_set,
+            component_type_set,
+            archetype_writer,
+            #[cfg(feature = "parallel")]
+            archetype_writer_key: None,
+            skip_if_unmatched: false,
+            #[cfg(feature = "parallel")]
+            priority: 0,
+            #[cfg(feature = "parallel")]
+            cost: None,
+        }
+    }
+
+    fn insert(mut self, system: System<'closures, Resources>) -> Self {
+        let id = SystemId(self.systems.len());
+        #[cfg(feature = "parallel")]
+        {
+            self.all_component_types
+                .extend(&system.component_type_set.immutable);
+            self.all_component_types
+                .extend(&system.component_type_set.mutable);
         }
+        self.systems.insert(id, system);
+        self
+    }
+
+    fn insert_with_handle<NewHandle>(
+        self,
+        system: System<'closures, Resources>,
+        handle: NewHandle,
+    ) -> ExecutorBuilder<'closures, Resources, NewHandle>
+    where
+        NewHandle: HandleConversion<Handle> + Debug,
+    {
+        let mut handles = NewHandle::convert_hash_map(self.handles);
+        if let Some(id) = handles.get(&handle) {
+            panic!(
+                "system {:?} already exists: {} cannot replace {}",
+                handle, system.name, self.systems[id].name
+            );
+        }
+        let id = SystemId(self.systems.len());
+        let builder = ExecutorBuilder {
+            systems: self.systems,
+            handles: HashMap::with_capacity(0),
+            #[cfg(feature = "parallel")]
+            all_component_types: self.all_component_types,
+            #[cfg(feature = "parallel")]
+            scheduling_policy: self.scheduling_policy,
+            #[cfg(feature = "parallel")]
+            max_concurrent_systems: self.max_concurrent_systems,
+            read_only: self.read_only,
+        }
+        .insert(system);
+        handles.insert(handle, id);
+        ExecutorBuilder { handles, ..builder }
+    }
+
+    fn insert_with_deps(
+        self,
+        mut system: System<'closures, Resources>,
+        dependencies: Vec<Handle>,
+    ) -> Self
+    where
+        Handle: Debug,
+    {
+        let name = system.name;
+        system
+            .dependencies
+            .extend(dependencies.iter().map(|dep_handle| {
+                *self.handles.get(dep_handle).unwrap_or_else(|| {
+                    panic!(
+                        "could not resolve dependencies of handle-less system {}: \
+                        no system {:?} found",
+                        name, dep_handle
+                    )
+                })
+            }));
+        self.insert(system)
+    }
+
+    fn insert_with_handle_and_deps(
+        mut self,
+        mut system: System<'closures, Resources>,
+        handle: Handle,
+        dependencies: Vec<Handle>,
+    ) -> Self
+    where
+        Handle: Debug,
+    {
+        if let Some(id) = self.handles.get(&handle) {
+            panic!(
+                "system {:?} already exists: {} cannot replace {}",
+                handle, system.name, self.systems[id].name
+            );
+        }
+        if dependencies.contains(&handle) {
+            panic!("system {:?} ({}) depends on itself", handle, system.name);
+        }
+        let name = system.name;
+        system
+            .dependencies
+            .extend(dependencies.iter().map(|dep_handle| {
+                *self.handles.get(dep_handle).unwrap_or_else(|| {
+                    panic!(
+                        "could not resolve dependencies of system {:?} ({}): \
+                        no system {:?} found",
+                        handle, name, dep_handle
+                    )
+                })
+            }));
+        let id = SystemId(self.systems.len());
+        self.handles.insert(handle, id);
+        self.insert(system)
     }
 
     /// Creates a new system from a closure or a function, and inserts it into the builder.
@@ -92,10 +268,15 @@ where
     /// The system-to-be must return nothing and have these 3 arguments:
     /// - [`SystemContext`](struct.SystemContext.html),
     /// - any tuple (up to 16) or a single one of "resources": references or mutable references
-    /// to `Send + Sync` values not contained in a [`hecs::World`](../hecs/struct.World.html)
-    /// that the system will be accessing,
+    ///   to `Send + Sync` values not contained in a [`hecs::World`](../hecs/struct.World.html)
+    ///   that the system will be accessing,
     /// - any tuple (up to 16) or a single one of [`QueryMarker`](struct.QueryMarker.html) that
-    /// represent the queries the system will be making.
+    ///   represent the queries the system will be making.
+    ///
+    /// Functions and closures with a different number or order of parameters can be
+    /// adapted with [`yaks::flexible()`](fn.flexible.html). This function itself only accepts
+    /// the three-argument form: its fixed signature is what allows closures to leave
+    /// the types of their parameters, such as the context, unannotated.
     ///
     /// Additionally, closures may mutably borrow from their environment for the lifetime
     /// of the executor, but must be `Send + Sync`.
@@ -104,6 +285,14 @@ where
     /// signature; e.g., if any number of systems require a `&f32` or a `&mut f32`,
     /// executor's generic parameter must contain `f32`.
     ///
+    /// The exception are resources requested as `Option<&R>` or `Option<&mut R>`: the system
+    /// will receive `None` if the executor's signature doesn't contain `R`, which allows
+    /// using the same system in executors with different resources. This requires all types
+    /// in the executor's signature to be `'static`. Note that only the system's own
+    /// requirements are optional: when running an executor with `Resources` from the
+    /// [`resources`](../resources/index.html) crate (`resources-interop` feature), every type
+    /// in the executor's signature must still be present, or the executor will panic.
+    ///
     /// # Example
     /// ```rust
     /// # use yaks::{QueryMarker, SystemContext, Executor};
@@ -149,24 +338,63 @@ where
     /// drop(executor); // This releases the borrow of `increment`.
     /// assert_eq!(increment, 3);
     /// ```
-    pub fn system<'a, Closure, ResourceRefs, Queries, Markers>(mut self, closure: Closure) -> Self
+    ///
+    /// With the `macros` feature enabled, resources and query markers that several systems
+    /// share can be grouped into a struct deriving `SystemParams`, which can then be used as
+    /// the resources argument (on its own, or in a tuple with other resources). The struct must
+    /// have named fields and at most one lifetime parameter; fields of type `QueryMarker`,
+    /// also when named through a type alias, are treated as queries, all other fields - as
+    /// resources.
+    /// ```rust
+    /// # #[cfg(feature = "macros")]
+    /// # {
+    /// # use yaks::{QueryMarker, SystemContext, SystemParams, Executor};
+    /// # let world = hecs::World::new();
+    /// # struct A;
+    /// # struct B;
+    /// # struct C;
+    /// #[derive(SystemParams)]
+    /// struct Shared<'a> {
+    ///     res_a: &'a A,
+    ///     res_b: &'a mut B,
+    ///     query_0: QueryMarker<(&'a B, &'a mut C)>,
+    /// }
+    ///
+    /// fn system_0(context: SystemContext, shared: Shared, _queries: ()) {
+    ///     // This system may read resource of type `A`, may read or write resource of type `B`,
+    ///     // and may prepare & execute queries of `(&B, &mut C)`.
+    ///     for (_entity, (b, c)) in context.query(shared.query_0).iter() {}
+    /// }
+    ///
+    /// fn system_1(context: SystemContext, (shared, res_c): (Shared, &C), _queries: ()) {
+    ///     // Same as above, and may additionally read resource of type `C`.
+    /// }
+    ///
+    /// let mut executor = Executor::<(A, B, C)>::builder()
+    ///     .system(system_0)
+    ///     .system(system_1)
+    ///     .build();
+    /// let (mut a, mut b, mut c) = (A, B, C);
+    /// executor.run(&world, (&mut a, &mut b, &mut c));
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    /// This function will panic if:
+    /// - the system's resource requirements are incompatible with themselves,
+    ///   e.g. `(&mut SomeResource, &SomeResource)`,
+    /// - a single query of the system borrows a component incompatibly with itself,
+    ///   e.g. `QueryMarker<(&mut Pos, &Pos)>`; separate queries of the system may borrow
+    ///   the same component in any way, as long as they aren't used at the same time.
+    pub fn system<'a, Closure, ResourceRefs, Queries, Markers>(self, closure: Closure) -> Self
     where
         Resources::Wrapped: 'a,
         Closure: FnMut(SystemContext<'a>, ResourceRefs, Queries) + Send + Sync + 'closures,
         ResourceRefs: Fetch<'a, Resources::Wrapped, Markers> + 'a,
         Queries: QueryBundle,
     {
-        let id = SystemId(self.systems.len());
         let system = Self::box_system::<'a, Closure, ResourceRefs, Queries, Markers>(closure);
-        #[cfg(feature = "parallel")]
-        {
-            self.all_component_types
-                .extend(&system.component_type_set.immutable);
-            self.all_component_types
-                .extend(&system.component_type_set.mutable);
-        }
-        self.systems.insert(id, system);
-        self
+        self.insert(system)
     }
 
     /// Creates a new system from a closure or a function, and inserts it into
@@ -245,9 +473,10 @@ where
     ///
     /// # Panics
     /// This function will panic if:
-    /// - a system with given handle is already present in the builder.
+    /// - a system with given handle is already present in the builder,
+    /// - the system's borrows are incompatible with themselves; see [`::system()`](#method.system).
     pub fn system_with_handle<'a, Closure, ResourceRefs, Queries, Markers, NewHandle>(
-        mut self,
+        self,
         closure: Closure,
         handle: NewHandle,
     ) -> ExecutorBuilder<'closures, Resources, NewHandle>
@@ -258,27 +487,8 @@ where
         Queries: QueryBundle,
         NewHandle: HandleConversion<Handle> + Debug,
     {
-        let mut handles = NewHandle::convert_hash_map(self.handles);
-        if handles.contains_key(&handle) {
-            panic!("system {:?} already exists", handle);
-        }
-        let id = SystemId(self.systems.len());
         let system = Self::box_system::<'a, Closure, ResourceRefs, Queries, Markers>(closure);
-        #[cfg(feature = "parallel")]
-        {
-            self.all_component_types
-                .extend(&system.component_type_set.immutable);
-            self.all_component_types
-                .extend(&system.component_type_set.mutable);
-        }
-        self.systems.insert(id, system);
-        handles.insert(handle, id);
-        ExecutorBuilder {
-            systems: self.systems,
-            handles,
-            #[cfg(feature = "parallel")]
-            all_component_types: self.all_component_types,
-        }
+        self.insert_with_handle(system, handle)
     }
 
     /// Creates a new system from a closure or a function, and inserts it into
@@ -294,9 +504,10 @@ where
     /// # Panics
     /// This function will panic if:
     /// - given list of dependencies contains a handle that
-    /// doesn't correspond to any system in the builder.
+    ///   doesn't correspond to any system in the builder,
+    /// - the system's borrows are incompatible with themselves; see [`::system()`](#method.system).
     pub fn system_with_deps<'a, Closure, ResourceRefs, Queries, Markers>(
-        mut self,
+        self,
         closure: Closure,
         dependencies: Vec<Handle>,
     ) -> Self
@@ -307,27 +518,8 @@ where
         Queries: QueryBundle,
         Handle: Eq + Hash + Debug,
     {
-        let id = SystemId(self.systems.len());
-        let mut system = Self::box_system::<'a, Closure, ResourceRefs, Queries, Markers>(closure);
-        #[cfg(feature = "parallel")]
-        {
-            self.all_component_types
-                .extend(&system.component_type_set.immutable);
-            self.all_component_types
-                .extend(&system.component_type_set.mutable);
-        }
-        system
-            .dependencies
-            .extend(dependencies.iter().map(|dep_handle| {
-                *self.handles.get(dep_handle).unwrap_or_else(|| {
-                    panic!(
-                    "could not resolve dependencies of a handle-less system: no system {:?} found",
-                    dep_handle
-                )
-                })
-            }));
-        self.systems.insert(id, system);
-        self
+        let system = Self::box_system::<'a, Closure, ResourceRefs, Queries, Markers>(closure);
+        self.insert_with_deps(system, dependencies)
     }
 
     /// Creates a new system from a closure or a function, and inserts it into
@@ -344,10 +536,11 @@ where
     /// This function will panic if:
     /// - a system with given handle is already present in the builder,
     /// - given list of dependencies contains a handle that
-    /// doesn't correspond to any system in the builder,
-    /// - given handle appears in given list of dependencies.
+    ///   doesn't correspond to any system in the builder,
+    /// - given handle appears in given list of dependencies,
+    /// - the system's borrows are incompatible with themselves; see [`::system()`](#method.system).
     pub fn system_with_handle_and_deps<'a, Closure, ResourceRefs, Queries, Markers>(
-        mut self,
+        self,
         closure: Closure,
         handle: Handle,
         dependencies: Vec<Handle>,
@@ -359,33 +552,454 @@ where
         Queries: QueryBundle,
         Handle: Eq + Hash + Debug,
     {
-        if self.handles.contains_key(&handle) {
-            panic!("system {:?} already exists", handle);
+        let system = Self::box_system::<'a, Closure, ResourceRefs, Queries, Markers>(closure);
+        self.insert_with_handle_and_deps(system, handle, dependencies)
+    }
+
+    /// Inserts an executor into the builder, to be ran as a single system.
+    ///
+    /// The nested executor's resources must be a tuple of types that are all present in this
+    /// executor's resources. To the rest of the systems, it appears to borrow the union of
+    /// resources and components borrowed by its own systems; archetypes are accounted for
+    /// conservatively, as all of those containing any of the borrowed components.
+    ///
+    /// The nested executor runs on the same thread pool as the outer one, starting its systems
+    /// from the thread the outer executor runs it on. While waiting for its systems to finish,
+    /// that thread runs other jobs of the pool instead of blocking, so nesting doesn't require
+    /// additional threads.
+    /// Forcing archetype recalculation of the outer executor doesn't affect the nested one,
+    /// since it's inaccessible after being inserted.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use yaks::Executor;
+    /// # let world = hecs::World::new();
+    /// struct Gravity(f32);
+    /// struct Speed(f32);
+    /// struct Volume(f32);
+    ///
+    /// let physics = Executor::<(Gravity, Speed)>::builder()
+    ///     .system(|_context, (gravity, speed): (&Gravity, &mut Speed), _queries: ()| {
+    ///         speed.0 += gravity.0;
+    ///     })
+    ///     .build();
+    /// let mut executor = Executor::<(Gravity, Speed, Volume)>::builder()
+    ///     .executor(physics)
+    ///     .system(|_context, volume: &mut Volume, _queries: ()| {
+    ///         volume.0 = 0.5;
+    ///     })
+    ///     .build();
+    /// let (mut gravity, mut speed, mut volume) = (Gravity(1.0), Speed(0.0), Volume(1.0));
+    /// executor.run(&world, (&mut gravity, &mut speed, &mut volume));
+    /// assert_eq!(speed.0, 1.0);
+    /// ```
+    pub fn executor<Nested, Markers>(self, executor: Executor<'closures, Nested>) -> Self
+    where
+        Nested: NestedResources<Resources::Wrapped, Markers> + 'closures,
+        Executor<'closures, Nested>: Send + Sync,
+    {
+        let system = Self::box_executor(executor);
+        self.insert(system)
+    }
+
+    /// Inserts an executor into the builder with given handle, to be ran as a single system;
+    /// see [`::executor()`](#method.executor) and
+    /// [`::system_with_handle()`](#method.system_with_handle).
+    ///
+    /// # Panics
+    /// This function will panic if:
+    /// - a system with given handle is already present in the builder.
+    pub fn executor_with_handle<Nested, Markers, NewHandle>(
+        self,
+        executor: Executor<'closures, Nested>,
+        handle: NewHandle,
+    ) -> ExecutorBuilder<'closures, Resources, NewHandle>
+    where
+        Nested: NestedResources<Resources::Wrapped, Markers> + 'closures,
+        Executor<'closures, Nested>: Send + Sync,
+        NewHandle: HandleConversion<Handle> + Debug,
+    {
+        let system = Self::box_executor(executor);
+        self.insert_with_handle(system, handle)
+    }
+
+    /// Inserts an executor into the builder with given dependencies, to be ran as a single
+    /// system; see [`::executor()`](#method.executor) and
+    /// [`::system_with_deps()`](#method.system_with_deps).
+    ///
+    /// # Panics
+    /// This function will panic if:
+    /// - given list of dependencies contains a handle that
+    ///   doesn't correspond to any system in the builder.
+    pub fn executor_with_deps<Nested, Markers>(
+        self,
+        executor: Executor<'closures, Nested>,
+        dependencies: Vec<Handle>,
+    ) -> Self
+    where
+        Nested: NestedResources<Resources::Wrapped, Markers> + 'closures,
+        Executor<'closures, Nested>: Send + Sync,
+        Handle: Eq + Hash + Debug,
+    {
+        let system = Self::box_executor(executor);
+        self.insert_with_deps(system, dependencies)
+    }
+
+    /// Inserts an executor into the builder with given handle and dependencies, to be ran
+    /// as a single system; see [`::executor()`](#method.executor) and
+    /// [`::system_with_handle_and_deps()`](#method.system_with_handle_and_deps).
+    ///
+    /// # Panics
+    /// This function will panic if:
+    /// - a system with given handle is already present in the builder,
+    /// - given list of dependencies contains a handle that
+    ///   doesn't correspond to any system in the builder,
+    /// - given handle appears in given list of dependencies.
+    pub fn executor_with_handle_and_deps<Nested, Markers>(
+        self,
+        executor: Executor<'closures, Nested>,
+        handle: Handle,
+        dependencies: Vec<Handle>,
+    ) -> Self
+    where
+        Nested: NestedResources<Resources::Wrapped, Markers> + 'closures,
+        Executor<'closures, Nested>: Send + Sync,
+        Handle: Eq + Hash + Debug,
+    {
+        let system = Self::box_executor(executor);
+        self.insert_with_handle_and_deps(system, handle, dependencies)
+    }
+
+    /// Names the system (or executor) that was inserted into the builder last.
+    ///
+    /// Every system has a name, shown in panic messages and returned by
+    /// [`SystemContext::name()`](struct.SystemContext.html#method.name) and
+    /// [`Executor::system_names()`](struct.Executor.html#method.system_names); by default,
+    /// it's the type name of the function or closure. Names don't have to be unique, and
+    /// can be given to systems with or without handles.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use yaks::Executor;
+    /// fn gravity(_context: yaks::SystemContext, _resources: (), _queries: ()) {}
+    ///
+    /// let executor = Executor::<()>::builder()
+    ///     .system(gravity)
+    ///     .system_with_handle(|_context, _resources: (), _queries: ()| {}, 0)
+    ///     .named("friction")
+    ///     .build();
+    /// assert!(executor.system_names()[0].ends_with("gravity"));
+    /// assert_eq!(executor.system_names()[1], "friction");
+    /// ```
+    ///
+    /// # Panics
+    /// This function will panic if:
+    /// - the builder has no systems.
+    pub fn named(mut self, name: &'static str) -> Self {
+        self.last_system("cannot name a system").name = name;
+        self
+    }
+
+    /// Marks the system (or executor) that was inserted into the builder last to be skipped
+    /// whenever none of its queries match any entities of the world it's ran with.
+    ///
+    /// A skipped system still counts as finished for systems that depend on it. Systems
+    /// that make no queries are never skipped, and marking a system that does some work
+    /// regardless of its queries, e.g. with its resources, will make that work conditional.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use yaks::{Executor, QueryMarker};
+    /// # let mut world = hecs::World::new();
+    /// struct Boss;
+    ///
+    /// let mut executor = Executor::<(u32,)>::builder()
+    ///     .system(|context, fights: &mut u32, bosses: QueryMarker<&Boss>| {
+    ///         *fights += context.query(bosses).iter().count() as u32;
+    ///     })
+    ///     .skip_if_unmatched()
+    ///     .build();
+    /// let mut fights = 0;
+    /// // The system is not ran: there are no bosses in the world.
+    /// executor.run(&world, &mut fights);
+    /// world.spawn((Boss,));
+    /// executor.run(&world, &mut fights);
+    /// assert_eq!(fights, 1);
+    /// ```
+    ///
+    /// # Panics
+    /// This function will panic if:
+    /// - the builder has no systems.
+    pub fn skip_if_unmatched(mut self) -> Self {
+        self.last_system("cannot mark a system to be skipped")
+            .skip_if_unmatched = true;
+        self
+    }
+
+    /// Sets the priority of the system (or executor) that was inserted into the builder last;
+    /// the default is `0`.
+    ///
+    /// When several systems are ready to run, the built-in scheduling policies start those
+    /// with higher priority first, before considering anything else; see
+    /// [`SystemInfo::priority()`](struct.SystemInfo.html#method.priority). Unlike making
+    /// other systems depend on a system to have it started earlier, this doesn't constrain
+    /// the order of execution.
+    ///
+    /// Has no effect if the default `parallel` feature is disabled.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use yaks::Executor;
+    /// # let world = hecs::World::new();
+    /// # struct Input;
+    /// # struct Audio;
+    /// let mut executor = Executor::<(Input, Audio)>::builder()
+    ///     .system(|_context, _audio: &mut Audio, _queries: ()| {})
+    ///     .system(|_context, _input: &mut Input, _queries: ()| {})
+    ///     .named("input")
+    ///     .priority(1)
+    ///     .build();
+    /// executor.run(&world, (&mut Input, &mut Audio));
+    /// ```
+    ///
+    /// # Panics
+    /// This function will panic if:
+    /// - the builder has no systems.
+    #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+    pub fn priority(mut self, priority: i32) -> Self {
+        let system = self.last_system("cannot set the priority of a system");
+        #[cfg(feature = "parallel")]
+        {
+            system.priority = priority;
         }
-        if dependencies.contains(&handle) {
-            panic!("system {:?} depends on itself", handle);
+        self
+    }
+
+    /// Sets the expected duration of the system (or executor) that was inserted into
+    /// the builder last.
+    ///
+    /// Scheduling policies see it as [`SystemInfo::cost()`](struct.SystemInfo.html#method.cost),
+    /// and it's used in place of the system's measured duration until the system is ran,
+    /// e.g. by the critical paths of the first run with [`CriticalPath`][cp].
+    ///
+    /// [cp]: struct.CriticalPath.html
+    ///
+    /// Has no effect if the default `parallel` feature is disabled.
+    ///
+    /// # Panics
+    /// This function will panic if:
+    /// - the builder has no systems.
+    #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+    pub fn cost(mut self, cost: Duration) -> Self {
+        let system = self.last_system("cannot set the cost of a system");
+        #[cfg(feature = "parallel")]
+        {
+            system.cost = Some(cost);
         }
-        let id = SystemId(self.systems.len());
-        let mut system = Self::box_system::<'a, Closure, ResourceRefs, Queries, Markers>(closure);
+        self
+    }
+
+    /// Declares that no system in the executor borrows the resource of given type mutably,
+    /// allowing [`Executor::run()`](struct.Executor.html#method.run) to accept a shared
+    /// reference to it.
+    ///
+    /// Systems are type-erased once inserted, and the executor's type only lists its resources,
+    /// so whether any of them borrows a resource mutably can't be known at compile time; instead,
+    /// declaring the resource read-only is checked against the systems when the executor is built,
+    /// and only declared resources may be passed by shared reference.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use yaks::Executor;
+    /// # let world = hecs::World::new();
+    /// let mut executor = Executor::<(f32, u32)>::builder()
+    ///     .system(|_context, (some_f32, some_u32): (&f32, &mut u32), _queries: ()| {
+    ///         *some_u32 += *some_f32 as u32;
+    ///     })
+    ///     .read_only::<f32, _>()
+    ///     .build();
+    /// let some_f32 = 1f32;
+    /// let mut some_u32 = 0u32;
+    /// executor.run(&world, (&some_f32, &mut some_u32));
+    /// assert_eq!(some_u32, 1);
+    /// ```
+    ///
+    /// # Panics
+    /// Building the executor will panic if:
+    /// - a system borrows a resource declared read-only mutably.
+    pub fn read_only<R0, M0>(mut self) -> Self
+    where
+        Resources::Wrapped: Contains<R0, M0>,
+    {
+        let key = <Resources::Wrapped as Contains<R0, M0>>::resource_key();
+        let resource = (key, type_name::<R0>());
+        if !self.read_only.contains(&resource) {
+            self.read_only.push(resource);
+        }
+        self
+    }
+
+    fn last_system(&mut self, action: &str) -> &mut System<'closures, Resources> {
+        let id = SystemId(
+            self.systems
+                .len()
+                .checked_sub(1)
+                .unwrap_or_else(|| panic!("{}: the builder has no systems", action)),
+        );
+        self.systems
+            .get_mut(&id)
+            .expect("system IDs should always be valid")
+    }
+
+    /// Adds the systems of given plugin to the builder; see [`Plugin`](trait.Plugin.html).
+    pub fn plugin<P0>(self, plugin: P0) -> Self
+    where
+        P0: Plugin<'closures, Resources, Handle>,
+    {
+        plugin.build(self)
+    }
+
+    /// Moves all systems of another builder into this one, keeping their handles and
+    /// dependencies; systems of `other` are treated as if inserted after those of `self`.
+    ///
+    /// The builders may use the same type of handles, or one or both may have none.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use yaks::Executor;
+    /// # let world = hecs::World::new();
+    /// # struct Speed(f32);
+    /// # struct Volume(f32);
+    /// let physics = Executor::<(Speed, Volume)>::builder()
+    ///     .system_with_handle(|_context, _speed: &mut Speed, _queries: ()| {}, "physics");
+    /// let audio = Executor::<(Speed, Volume)>::builder()
+    ///     .system_with_handle(|_context, _volume: &mut Volume, _queries: ()| {}, "audio")
+    ///     .system_with_deps(|_context, _: (&Speed, &Volume), _queries: ()| {}, vec!["audio"]);
+    /// let mut executor = physics.merge(audio).build();
+    /// executor.run(&world, (&mut Speed(0.0), &mut Volume(1.0)));
+    /// ```
+    ///
+    /// # Panics
+    /// This function will panic if:
+    /// - a handle is present in both builders.
+    pub fn merge<OtherHandle>(
+        mut self,
+        other: ExecutorBuilder<'closures, Resources, OtherHandle>,
+    ) -> ExecutorBuilder<'closures, Resources, Handle::Output>
+    where
+        Handle: HandleMerge<OtherHandle>,
+        OtherHandle: Eq + Hash,
+    {
+        let offset = self.systems.len();
+        let ExecutorBuilder {
+            systems,
+            handles,
+            #[cfg(feature = "parallel")]
+            all_component_types,
+            read_only,
+            ..
+        } = other;
+        for (SystemId(id), mut system) in systems {
+            for SystemId(dependency) in &mut system.dependencies {
+                *dependency += offset;
+            }
+            self.systems.insert(SystemId(id + offset), system);
+        }
+        let handles = handles
+            .into_iter()
+            .map(|(handle, SystemId(id))| (handle, SystemId(id + offset)))
+            .collect();
+        #[cfg(feature = "parallel")]
+        self.all_component_types.extend(all_component_types);
+        for resource in read_only {
+            if !self.read_only.contains(&resource) {
+                self.read_only.push(resource);
+            }
+        }
+        let systems = &self.systems;
+        let handles = Handle::merge_hash_maps(self.handles, handles, &|id| systems[&id].name);
+        ExecutorBuilder {
+            systems: self.systems,
+            handles,
+            #[cfg(feature = "parallel")]
+            all_component_types: self.all_component_types,
+            #[cfg(feature = "parallel")]
+            scheduling_policy: self.scheduling_policy,
+            #[cfg(feature = "parallel")]
+            max_concurrent_systems: self.max_concurrent_systems,
+            read_only: self.read_only,
+        }
+    }
+
+    /// Sets the policy that decides which of the systems that are ready to run are started,
+    /// and in which order; see [`SchedulingPolicy`](trait.SchedulingPolicy.html). The default
+    /// is [`DependantsLength`](struct.DependantsLength.html). The policy of `other` is
+    /// discarded when merging builders.
+    ///
+    /// Has no effect if the default `parallel` feature is disabled.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use yaks::{CriticalPath, Executor};
+    /// # let world = hecs::World::new();
+    /// let mut executor = Executor::<()>::builder()
+    ///     .system_with_handle(|_context, _resources: (), _queries: ()| {}, "physics")
+    ///     .system_with_deps(|_context, _resources: (), _queries: ()| {}, vec!["physics"])
+    ///     .system(|_context, _resources: (), _queries: ()| {})
+    ///     .scheduling_policy(CriticalPath)
+    ///     .build();
+    /// executor.run(&world, ());
+    /// ```
+    #[cfg_attr(not(feature = "parallel"), allow(unused_mut, unused_variables))]
+    pub fn scheduling_policy<P0>(mut self, policy: P0) -> Self
+    where
+        P0: SchedulingPolicy + 'closures,
+    {
         #[cfg(feature = "parallel")]
         {
-            self.all_component_types
-                .extend(&system.component_type_set.immutable);
-            self.all_component_types
-                .extend(&system.component_type_set.mutable);
+            self.scheduling_policy = Some(Box::new(policy));
+        }
+        self
+    }
+
+    /// Limits how many systems the executor runs at the same time; by default, it's limited
+    /// only by the amount of threads in the `rayon` thread pool.
+    ///
+    /// This keeps the executor from occupying every thread of the pool when other work
+    /// shares it, e.g. another executor ran alongside. A limit of `1` runs the systems
+    /// one at a time, in an order that still respects their dependencies, which can help
+    /// with debugging. Systems of a nested executor count as one system, and are limited
+    /// by the nested executor's own setting; jobs spawned by
+    /// [`yaks::batch()`](fn.batch.html) are not counted. The limit of `other` is discarded
+    /// when merging builders.
+    ///
+    /// Has no effect if the default `parallel` feature is disabled.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use yaks::Executor;
+    /// # let world = hecs::World::new();
+    /// let mut executor = Executor::<()>::builder()
+    ///     .system(|_context, _resources: (), _queries: ()| {})
+    ///     .system(|_context, _resources: (), _queries: ()| {})
+    ///     .system(|_context, _resources: (), _queries: ()| {})
+    ///     .max_concurrent_systems(2)
+    ///     .build();
+    /// executor.run(&world, ());
+    /// ```
+    ///
+    /// # Panics
+    /// This function will panic if:
+    /// - the limit is `0`.
+    #[cfg_attr(not(feature = "parallel"), allow(unused_mut))]
+    pub fn max_concurrent_systems(mut self, limit: usize) -> Self {
+        if limit == 0 {
+            panic!("cannot limit the amount of concurrent systems to 0");
+        }
+        #[cfg(feature = "parallel")]
+        {
+            self.max_concurrent_systems = limit;
         }
-        system
-            .dependencies
-            .extend(dependencies.iter().map(|dep_handle| {
-                *self.handles.get(dep_handle).unwrap_or_else(|| {
-                    panic!(
-                        "could not resolve dependencies of system {:?}: no system {:?} found",
-                        handle, dep_handle
-                    )
-                })
-            }));
-        self.systems.insert(id, system);
-        self.handles.insert(handle, id);
         self
     }
 
@@ -395,6 +1009,19 @@ where
     }
 }
 
+/// Sets archetype bits of systems with given queries and resources; shared by all of them,
+/// see `System::archetype_writer_key`.
+fn write_archetype_bits<'a, Wrapped, ResourceRefs, Queries, Markers>(
+    world: &World,
+    archetype_set: &mut ArchetypeSet,
+) where
+    ResourceRefs: Fetch<'a, Wrapped, Markers>,
+    Queries: QueryBundle,
+{
+    Queries::set_archetype_bits(world, archetype_set);
+    ResourceRefs::set_archetype_bits(world, archetype_set);
+}
+
 #[derive(PartialEq, Eq, Hash)]
 pub struct DummyHandle;
 
@@ -419,3 +1046,81 @@ where
         map
     }
 }
+
+pub trait HandleMerge<T>: Sized {
+    type Output: Eq + Hash;
+
+    fn merge_hash_maps(
+        map: HashMap<Self, SystemId>,
+        other: HashMap<T, SystemId>,
+        name: &dyn Fn(SystemId) -> &'static str,
+    ) -> HashMap<Self::Output, SystemId>;
+}
+
+impl HandleMerge<DummyHandle> for DummyHandle {
+    type Output = DummyHandle;
+
+    fn merge_hash_maps(
+        _: HashMap<Self, SystemId>,
+        _: HashMap<DummyHandle, SystemId>,
+        _: &dyn Fn(SystemId) -> &'static str,
+    ) -> HashMap<Self::Output, SystemId> {
+        HashMap::with_capacity(0)
+    }
+}
+
+impl<T> HandleMerge<T> for DummyHandle
+where
+    T: Debug + Eq + Hash,
+{
+    type Output = T;
+
+    fn merge_hash_maps(
+        _: HashMap<Self, SystemId>,
+        other: HashMap<T, SystemId>,
+        _: &dyn Fn(SystemId) -> &'static str,
+    ) -> HashMap<Self::Output, SystemId> {
+        other
+    }
+}
+
+impl<T> HandleMerge<DummyHandle> for T
+where
+    T: Debug + Eq + Hash,
+{
+    type Output = T;
+
+    fn merge_hash_maps(
+        map: HashMap<Self, SystemId>,
+        _: HashMap<DummyHandle, SystemId>,
+        _: &dyn Fn(SystemId) -> &'static str,
+    ) -> HashMap<Self::Output, SystemId> {
+        map
+    }
+}
+
+impl<T> HandleMerge<T> for T
+where
+    T: Debug + Eq + Hash,
+{
+    type Output = T;
+
+    fn merge_hash_maps(
+        mut map: HashMap<Self, SystemId>,
+        other: HashMap<T, SystemId>,
+        name: &dyn Fn(SystemId) -> &'static str,
+    ) -> HashMap<Self::Output, SystemId> {
+        for (handle, id) in other {
+            if let Some(existing) = map.get(&handle) {
+                panic!(
+                    "cannot merge builders: system {:?} exists in both of them, as {} and {}",
+                    handle,
+                    name(*existing),
+                    name(id)
+                );
+            }
+            map.insert(handle, id);
+        }
+        map
+    }
+}
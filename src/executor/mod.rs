@@ -1,13 +1,23 @@
+use fixedbitset::FixedBitSet;
 use hecs::World;
 use std::collections::HashMap;
 
-use crate::{RefExtractor, ResourceTuple, SystemContext};
+use crate::{
+    ArchetypeSet, BorrowSet, BorrowTypeSet, RefExtractor, ResourceKey, ResourceTuple,
+    SystemContext, TypeSet,
+};
 
 mod builder;
+mod owning;
+mod plugin;
+mod scheduling_policy;
 
 use builder::DummyHandle;
 
 pub use builder::ExecutorBuilder;
+pub use owning::OwningExecutor;
+pub use plugin::Plugin;
+pub use scheduling_policy::{CriticalPath, DependantsLength, SchedulingPolicy, SystemInfo};
 
 #[cfg(not(feature = "parallel"))]
 mod sequential;
@@ -18,13 +28,13 @@ use sequential::ExecutorSequential;
 #[cfg(feature = "parallel")]
 mod parallel;
 
-#[cfg(feature = "parallel")]
-use crate::TypeSet;
 #[cfg(feature = "parallel")]
 use parallel::ExecutorParallel;
 
 type SystemClosure<'closure, Cells> = dyn FnMut(SystemContext, &Cells) + Send + Sync + 'closure;
 
+type ArchetypeWriter = dyn Fn(&World, &mut ArchetypeSet) + Send + Sync;
+
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct SystemId(pub(crate) usize);
 
@@ -33,10 +43,10 @@ pub struct SystemId(pub(crate) usize);
 /// Systems can be any closure or function that return nothing and have these 3 arguments:
 /// - [`SystemContext`](struct.SystemContext.html),
 /// - any tuple (up to 16) or a single one of "resources": references or mutable references
-/// to `Send + Sync` values not contained in a [`hecs::World`](../hecs/struct.World.html)
-/// that the system will be accessing,
+///   to `Send + Sync` values not contained in a [`hecs::World`](../hecs/struct.World.html)
+///   that the system will be accessing,
 /// - any tuple (up to 16) or a single one of [`QueryMarker`](struct.QueryMarker.html) that
-/// represent the queries the system will be making.
+///   represent the queries the system will be making.
 ///
 /// Additionally, closures may mutably borrow from their environment for the lifetime `'closures`
 /// of the executor, but must be `Send + Sync`. If none of the systems make any borrows from the
@@ -47,6 +57,8 @@ pub struct SystemId(pub(crate) usize);
 /// however, any number of systems in the executor may have either an immutable or a mutable
 /// reference of said type in their signature. For example: if any number of systems require
 /// a `&f32` or a `&mut f32`, `Resources` must contain `f32`.
+/// Systems that require an `Option<&f32>` or an `Option<&mut f32>` will receive `None` if
+/// `Resources` doesn't contain `f32`.
 ///
 /// It's possible to define an order of execution of the systems by building up a dependency
 /// graph when building the executor, see [`ExecutorBuilder::system_with_handle()`][swh].
@@ -67,6 +79,15 @@ where
     Resources: ResourceTuple,
 {
     pub(crate) borrows: Resources::BorrowTuple,
+    /// Names of contained systems, in order of insertion.
+    pub(crate) system_names: Vec<&'static str>,
+    /// Union of resource sets of all contained systems.
+    pub(crate) resource_set: BorrowSet,
+    /// Union of component type sets of all contained systems.
+    pub(crate) component_type_set: BorrowTypeSet,
+    /// Resources that may be passed in by shared reference;
+    /// see `ExecutorBuilder::read_only()`.
+    pub(crate) read_only: FixedBitSet,
     #[cfg(feature = "parallel")]
     pub(crate) inner: ExecutorParallel<'closures, Resources>,
     #[cfg(not(feature = "parallel"))]
@@ -84,12 +105,71 @@ where
             handles: HashMap::with_capacity(0),
             #[cfg(feature = "parallel")]
             all_component_types: TypeSet::new(),
+            #[cfg(feature = "parallel")]
+            scheduling_policy: None,
+            #[cfg(feature = "parallel")]
+            max_concurrent_systems: usize::MAX,
+            read_only: vec![],
         }
     }
 
-    pub(crate) fn build<Handle>(builder: ExecutorBuilder<'closures, Resources, Handle>) -> Self {
+    pub(crate) fn build<Handle>(
+        mut builder: ExecutorBuilder<'closures, Resources, Handle>,
+    ) -> Self {
+        // Resources identified by type are assigned indices after those of the tuple.
+        let mut resource_types = TypeSet::new();
+        for system in builder.systems.values() {
+            let types = &system.resource_set.types;
+            resource_types.extend(types.immutable.union(&types.mutable));
+        }
+        for (key, _) in &builder.read_only {
+            if let ResourceKey::Type(id) = key {
+                resource_types.insert(*id);
+            }
+        }
+        let mut resource_types: Vec<_> = resource_types.into_iter().collect();
+        resource_types.sort();
+        let index_of = |key: ResourceKey| match key {
+            ResourceKey::Index(index) => index,
+            ResourceKey::Type(id) => {
+                let position = resource_types.iter().position(|element| *element == id);
+                Resources::LENGTH + position.expect("all resource types should be collected")
+            }
+        };
+        let mut resource_set = BorrowSet::with_capacity(Resources::LENGTH);
+        let mut component_type_set = BorrowTypeSet::new();
+        let mut system_names = vec![""; builder.systems.len()];
+        for (SystemId(id), system) in &mut builder.systems {
+            system
+                .resource_set
+                .condense_types(&resource_types, Resources::LENGTH);
+            system_names[*id] = system.name;
+            resource_set.extend(&system.resource_set);
+            component_type_set.extend(&system.component_type_set);
+        }
+        let mut read_only = FixedBitSet::with_capacity(Resources::LENGTH);
+        for &(key, resource) in &builder.read_only {
+            let index = index_of(key);
+            let mutated = |id: &usize| {
+                let system = &builder.systems[&SystemId(*id)];
+                system.resource_set.mutable.contains(index)
+            };
+            if let Some(id) = (0..system_names.len()).find(mutated) {
+                panic!(
+                    "cannot build the executor: system {} borrows resource {} mutably, \
+                    but it's declared read-only",
+                    system_names[id], resource
+                );
+            }
+            read_only.grow(index + 1);
+            read_only.insert(index);
+        }
         Self {
             borrows: Resources::instantiate_borrows(),
+            system_names,
+            resource_set,
+            component_type_set,
+            read_only,
             #[cfg(feature = "parallel")]
             inner: ExecutorParallel::build(builder),
             #[cfg(not(feature = "parallel"))]
@@ -125,6 +205,26 @@ where
         self.inner.force_archetype_recalculation();
     }
 
+    /// Returns the name of the system with given ID; see
+    /// [`ExecutorBuilder::named()`](struct.ExecutorBuilder.html#method.named).
+    pub fn system_name(&self, id: SystemId) -> Option<&'static str> {
+        self.system_names.get(id.0).copied()
+    }
+
+    /// Returns the names of all contained systems, in order of their insertion
+    /// into the builder; see
+    /// [`ExecutorBuilder::named()`](struct.ExecutorBuilder.html#method.named).
+    pub fn system_names(&self) -> &[&'static str] {
+        &self.system_names
+    }
+
+    /// Moves the executor and given resources into an
+    /// [`OwningExecutor`](struct.OwningExecutor.html), which stores the resources
+    /// and doesn't require them to be passed in when ran.
+    pub fn with_resources(self, resources: Resources) -> OwningExecutor<'closures, Resources> {
+        OwningExecutor::new(self, resources)
+    }
+
     /// Executes all of the contained systems once, running as much of them at the same time
     /// as their resource use, queries, and dependencies allow.
     ///
@@ -135,7 +235,7 @@ where
     ///
     /// [swh]: struct.ExecutorBuilder.html#method.system_with_handle
     ///
-    /// The `resources` argument when calling this function must be a tuple of exclusive references
+    /// The `resources` argument when calling this function must be a tuple of references
     /// to values of types specified by the generic parameter `Resources` of the executor:
     /// ```rust
     /// # use yaks::Executor;
@@ -155,8 +255,41 @@ where
     /// let mut executor = Executor::<()>::builder().build();
     /// executor.run(&world, ());
     /// ```
-    /// In the future, exclusivity requirement might be relaxed for resources that aren't mutated
-    /// by any of the systems, but doing that as of writing is unfeasible.
+    /// References in the tuple must be exclusive, unless the corresponding resource is declared
+    /// read-only (see [`ExecutorBuilder::read_only()`][ro]), in which case a shared reference
+    /// is enough:
+    ///
+    /// [ro]: struct.ExecutorBuilder.html#method.read_only
+    /// ```rust
+    /// # use yaks::Executor;
+    /// # let world = hecs::World::new();
+    /// let mut executor = Executor::<(f32, u32)>::builder()
+    ///     .system(|_context, (some_f32, some_u32): (&f32, &mut u32), _queries: ()| {
+    ///         *some_u32 += *some_f32 as u32;
+    ///     })
+    ///     .read_only::<f32, _>()
+    ///     .build();
+    /// let some_f32 = 1f32;
+    /// let mut some_u32 = 0u32;
+    /// executor.run(&world, (&some_f32, &mut some_u32));
+    /// assert_eq!(some_u32, 1);
+    /// ```
+    /// The tuple may also contain references to more resources than the executor requires,
+    /// in any order; the required ones are picked out by type, and a missing one is
+    /// a compile error. The same goes for a mutable reference to a tuple of the resources
+    /// themselves, or to a struct deriving `ResourceBundle`
+    /// (see [`ResourceSource`](trait.ResourceSource.html)):
+    /// ```rust
+    /// # use yaks::Executor;
+    /// # let world = hecs::World::new();
+    /// let mut executor = Executor::<(f32, u32)>::builder().build();
+    /// let (mut some_f32, mut some_u32, mut some_u64) = (0f32, 0u32, 0u64);
+    /// executor.run(&world, (&mut some_u64, &mut some_u32, &mut some_f32));
+    /// let mut all = (0u64, 0u32, 0f32);
+    /// executor.run(&world, &mut all);
+    /// ```
+    /// Other kinds of resource containers can be used in place of the tuple, see
+    /// [`RefExtractor`](trait.RefExtractor.html).
     ///
     /// This function can be called inside a
     /// [`rayon::ThreadPool::install()`](../rayon/struct.ThreadPool.html#method.install) block
@@ -188,16 +321,15 @@ where
     ///
     /// # Panics
     /// This function will panic if:
-    /// - a system within the executor has resource requirements that are incompatible with itself,
-    /// e.g. `(&mut SomeResource, &SomeResource)`.
+    /// - a shared reference is given for a resource that isn't declared read-only.
     ///
     /// Additionally, it *may* panic if:
     /// - a different [`hecs::World`](../hecs/struct.World.html) is supplied than
-    /// in a previous call, without first calling
-    /// [`::force_archetype_recalculation()`](#method.force_archetype_recalculation).
-    pub fn run<RefSource>(&mut self, world: &World, resources: RefSource)
+    ///   in a previous call, without first calling
+    ///   [`::force_archetype_recalculation()`](#method.force_archetype_recalculation).
+    pub fn run<RefSource, Marker>(&mut self, world: &World, resources: RefSource)
     where
-        Resources: RefExtractor<RefSource>,
+        Resources: RefExtractor<RefSource, Marker>,
     {
         Resources::extract_and_run(self, world, resources);
     }
@@ -0,0 +1,118 @@
+use hecs::World;
+use std::any::type_name;
+
+use crate::{
+    resource::{Contains, OwnedResources},
+    Executor, RefExtractor, ResourceTuple,
+};
+
+/// An [`Executor`](struct.Executor.html) that stores its resources, created with
+/// [`Executor::with_resources()`](struct.Executor.html#method.with_resources).
+///
+/// It's ran without passing the resources, and provides access to them between runs.
+///
+/// # Example
+/// ```rust
+/// # use yaks::Executor;
+/// # let world = hecs::World::new();
+/// struct Time(f32);
+///
+/// let mut executor = Executor::<(Time, u32)>::builder()
+///     .system(|_context, (time, frames): (&mut Time, &mut u32), _queries: ()| {
+///         time.0 += 0.5;
+///         *frames += 1;
+///     })
+///     .build()
+///     .with_resources((Time(0.0), 0));
+/// executor.run(&world);
+/// executor.run(&world);
+/// assert_eq!(executor.resource::<Time, _>().0, 1.0);
+/// *executor.resource_mut::<u32, _>() = 0;
+/// let (_executor, (_time, frames)) = executor.into_parts();
+/// assert_eq!(frames, 0);
+/// ```
+pub struct OwningExecutor<'closures, Resources>
+where
+    Resources: ResourceTuple,
+{
+    executor: Executor<'closures, Resources>,
+    resources: Resources,
+}
+
+impl<'closures, Resources> OwningExecutor<'closures, Resources>
+where
+    Resources: ResourceTuple,
+{
+    pub(crate) fn new(executor: Executor<'closures, Resources>, resources: Resources) -> Self {
+        Self {
+            executor,
+            resources,
+        }
+    }
+
+    /// Executes all of the contained systems once, using the stored resources;
+    /// see [`Executor::run()`](struct.Executor.html#method.run).
+    pub fn run<Marker>(&mut self, world: &World)
+    where
+        for<'a> Resources: RefExtractor<&'a mut Resources, Marker>,
+    {
+        self.executor.run(world, &mut self.resources);
+    }
+
+    /// Forces the executor to forget stored archetypes generation, see
+    /// [`Executor::force_archetype_recalculation()`][farc].
+    ///
+    /// [farc]: struct.Executor.html#method.force_archetype_recalculation
+    pub fn force_archetype_recalculation(&mut self) {
+        self.executor.force_archetype_recalculation();
+    }
+
+    /// Returns a reference to the stored resource of given type; asking for a type that
+    /// isn't in the executor's tuple of resources is a compile error.
+    ///
+    /// # Panics
+    /// Panics if the executor stores a [`ResourceMap`](struct.ResourceMap.html) that doesn't
+    /// contain a resource of that type.
+    pub fn resource<R0, M0>(&self) -> &R0
+    where
+        Resources: OwnedResources,
+        Resources::Wrapped: Contains<R0, M0>,
+        R0: Send + Sync + 'static,
+    {
+        self.resources
+            .get()
+            .unwrap_or_else(|| panic!("no resource of type {} in the executor", type_name::<R0>()))
+    }
+
+    /// Returns a mutable reference to the stored resource of given type; asking for a type
+    /// that isn't in the executor's tuple of resources is a compile error.
+    ///
+    /// # Panics
+    /// Panics if the executor stores a [`ResourceMap`](struct.ResourceMap.html) that doesn't
+    /// contain a resource of that type.
+    pub fn resource_mut<R0, M0>(&mut self) -> &mut R0
+    where
+        Resources: OwnedResources,
+        Resources::Wrapped: Contains<R0, M0>,
+        R0: Send + Sync + 'static,
+    {
+        self.resources
+            .get_mut()
+            .unwrap_or_else(|| panic!("no resource of type {} in the executor", type_name::<R0>()))
+    }
+
+    /// Returns a reference to all of the stored resources.
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Returns a mutable reference to all of the stored resources.
+    pub fn resources_mut(&mut self) -> &mut Resources {
+        &mut self.resources
+    }
+
+    /// Separates the executor from the stored resources.
+    pub fn into_parts(self) -> (Executor<'closures, Resources>, Resources) {
+        (self.executor, self.resources)
+    }
+}
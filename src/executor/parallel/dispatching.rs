@@ -1,18 +1,23 @@
 use hecs::World;
 use parking_lot::Mutex;
 use rayon::prelude::*;
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 
 use super::SystemClosure;
 use crate::{ResourceTuple, SystemContext, SystemId};
 
 /// Parallel executor variant, used when all systems are proven to be statically disjoint,
-/// and have no dependencies.
+/// have no dependencies, and can all be started at once without being ordered.
 pub struct Dispatcher<'closures, Resources>
 where
     Resources: ResourceTuple,
 {
-    pub systems: HashMap<SystemId, Arc<Mutex<SystemClosure<'closures, Resources::Wrapped>>>>,
+    #[allow(clippy::type_complexity)]
+    pub systems: Vec<(
+        SystemId,
+        &'static str,
+        Arc<Mutex<SystemClosure<'closures, Resources::Wrapped>>>,
+    )>,
 }
 
 impl<'closures, Resources> Dispatcher<'closures, Resources>
@@ -20,19 +25,23 @@ where
     Resources: ResourceTuple,
 {
     pub fn run(&mut self, world: &World, wrapped: Resources::Wrapped) {
-        // All systems are statically disjoint, so they can all be running together at all times.
-        self.systems.par_iter().for_each(|(id, system)| {
+        type System<'a, Cells> = (SystemId, &'static str, Arc<Mutex<SystemClosure<'a, Cells>>>);
+        let run_system = |(id, name, system): &System<'closures, Resources::Wrapped>| {
             let system = &mut *system
                 .try_lock() // TODO should this be .lock() instead?
                 .expect("systems should only be ran once per execution");
             system(
                 SystemContext {
                     system_id: Some(*id),
+                    system_name: Some(name),
                     world,
                 },
                 &wrapped,
             );
-        });
+        };
+        // All systems are statically disjoint, so they can all be running together
+        // at all times.
+        self.systems.par_iter().for_each(run_system);
     }
 }
 
@@ -3,44 +3,110 @@ use parking_lot::Mutex;
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
+    time::Duration,
 };
 
-use super::SystemClosure;
-use crate::{ArchetypeSet, BorrowSet, ExecutorBuilder, ResourceTuple, SystemId};
+use super::{ArchetypeWriter, SystemClosure};
+use crate::{
+    ArchetypeSet, BorrowSet, DependantsLength, ExecutorBuilder, ResourceTuple, SystemId, SystemInfo,
+};
 
 mod dispatching;
 mod scheduling;
 
 use dispatching::Dispatcher;
-use scheduling::{DependantsLength, Scheduler};
+use scheduling::Scheduler;
 
 static DISCONNECTED: &str = "channel should not be disconnected at this point";
 static INVALID_ID: &str = "system IDs should always be valid";
 
+/// Weight of the latest measured duration of a system in its moving average.
+const DURATION_SMOOTHING: f64 = 0.2;
+
 /// System closure and scheduling metadata container.
 pub struct System<'closure, Resources>
 where
     Resources: ResourceTuple,
 {
     pub closure: Arc<Mutex<SystemClosure<'closure, Resources::Wrapped>>>,
+    pub name: &'static str,
     pub resource_set: BorrowSet,
     pub component_set: BorrowSet,
-    pub archetype_set: ArchetypeSet,
-    pub archetype_writer: Box<dyn Fn(&World, &mut ArchetypeSet) + Send>,
+    /// Index of the system's archetype set in the scheduler.
+    pub archetype_set: usize,
+    /// Whether the system is skipped when none of the archetypes its archetype set matches
+    /// contain entities.
+    pub skip_if_unmatched: bool,
+    /// Whether the system is skipped in the current world's archetypes;
+    /// updated with the archetype sets.
+    pub skipped: bool,
     pub dependants: Vec<SystemId>,
     pub dependencies: usize,
     pub unsatisfied_dependencies: usize,
+    pub priority: i32,
+    /// Expected duration of the system, used until it's measured.
+    pub cost: Option<Duration>,
+    pub last_duration: Option<Duration>,
+    /// Exponential moving average of the system's measured durations.
+    pub average_duration: Option<Duration>,
+    /// Longest sum of average durations along a chain of dependants, including the system.
+    pub critical_path: Duration,
+}
+
+impl<'closure, Resources> System<'closure, Resources>
+where
+    Resources: ResourceTuple,
+{
+    /// Collects the metadata given to scheduling policies, and used to check if systems
+    /// may run concurrently given current archetype sets.
+    fn info<'a>(
+        &'a self,
+        id: SystemId,
+        archetype_sets: &'a [SharedArchetypeSet],
+    ) -> SystemInfo<'a> {
+        SystemInfo {
+            id,
+            name: self.name,
+            dependants: self.dependants.len(),
+            priority: self.priority,
+            cost: self.cost,
+            last_duration: self.last_duration,
+            average_duration: self.average_duration,
+            critical_path: self.critical_path,
+            resource_set: &self.resource_set,
+            component_set: &self.component_set,
+            archetype_set: &archetype_sets[self.archetype_set].set,
+        }
+    }
+
+    /// Updates the last and average durations with a new measurement.
+    fn record_duration(&mut self, duration: Duration) {
+        self.last_duration = Some(duration);
+        self.average_duration = Some(match self.average_duration {
+            Some(average) => {
+                average.mul_f64(1.0 - DURATION_SMOOTHING) + duration.mul_f64(DURATION_SMOOTHING)
+            }
+            None => duration,
+        });
+    }
+}
+
+/// Archetype set of one or more systems, and the function that updates it.
+pub struct SharedArchetypeSet {
+    pub set: ArchetypeSet,
+    pub writer: Box<ArchetypeWriter>,
 }
 
 /// Variants of parallel executor, chosen based on properties of systems in the builder.
+#[allow(clippy::large_enum_variant)]
 pub enum ExecutorParallel<'closures, Resources>
 where
     Resources: ResourceTuple,
 {
     // TODO consider more granularity:
     // scheduler, disjoint scheduler, dispatcher (has to be disjoint either way)
-    /// Used when all systems are proven to be statically disjoint
-    /// and have no dependencies.
+    /// Used when all systems are proven to be statically disjoint, have no dependencies,
+    /// and can all be started at once without being ordered.
     Dispatching(Dispatcher<'closures, Resources>),
     /// Used when systems cannot be proven to be statically disjoint,
     /// or have dependencies.
@@ -58,10 +124,15 @@ where
         let ExecutorBuilder {
             mut systems,
             mut all_component_types,
+            scheduling_policy,
+            max_concurrent_systems,
             ..
         } = builder;
         // This guarantees iteration order; TODO probably not necessary?..
-        let all_component_types = all_component_types.drain().collect::<Vec<_>>();
+        let all_component_types: Arc<[_]> = all_component_types.drain().collect();
+        // Systems with identical archetype writers share archetype sets.
+        let mut archetype_sets = Vec::new();
+        let mut archetype_set_indices = HashMap::new();
         let mut systems: HashMap<SystemId, System<'closures, Resources>> = systems
             .drain()
             .map(|(id, system)| {
@@ -71,23 +142,56 @@ where
                     systems_without_dependencies.push(id);
                 }
                 all_dependencies.push((id, system.dependencies));
+                let key = system.archetype_writer_key;
+                let archetype_set = match key.and_then(|key| archetype_set_indices.get(&key)) {
+                    Some(index) => *index,
+                    None => {
+                        archetype_sets.push(SharedArchetypeSet {
+                            set: ArchetypeSet::new(all_component_types.clone()),
+                            writer: system.archetype_writer,
+                        });
+                        if let Some(key) = key {
+                            archetype_set_indices.insert(key, archetype_sets.len() - 1);
+                        }
+                        archetype_sets.len() - 1
+                    }
+                };
+                let skip_if_unmatched =
+                    system.skip_if_unmatched && !system.component_type_set.is_empty();
                 (
                     id,
                     System {
                         closure: Arc::new(Mutex::new(system.closure)),
+                        name: system.name,
                         resource_set: system.resource_set,
                         component_set: system.component_type_set.condense(&all_component_types),
-                        archetype_set: ArchetypeSet::default(),
-                        archetype_writer: system.archetype_writer,
+                        archetype_set,
+                        skip_if_unmatched,
+                        skipped: false,
                         dependants: vec![],
                         dependencies,
                         unsatisfied_dependencies: 0,
+                        priority: system.priority,
+                        cost: system.cost,
+                        last_duration: None,
+                        average_duration: None,
+                        critical_path: Duration::default(),
                     },
                 )
             })
             .collect();
-        // If all systems are independent, it might be possible to use dispatching heuristic.
-        if systems.len() == systems_without_dependencies.len() {
+        // Systems that are all started at once aren't ordered in any way, so this is only
+        // possible if neither a scheduling policy nor priorities would order them.
+        let dispatch_allowed =
+            scheduling_policy.is_none() && systems.values().all(|system| system.priority == 0);
+        let scheduling_policy = scheduling_policy.unwrap_or_else(|| Box::new(DependantsLength));
+        // If all systems are independent, it might be possible to use dispatching heuristic;
+        // the dispatcher doesn't examine archetypes, so it can't skip systems.
+        if dispatch_allowed
+            && systems.len() == systems_without_dependencies.len()
+            && systems.len() <= max_concurrent_systems
+            && systems.values().all(|system| !system.skip_if_unmatched)
+        {
             let mut tested_ids = Vec::new();
             let mut all_disjoint = true;
             'outer: for (id, system) in &systems {
@@ -106,7 +210,7 @@ where
                 return ExecutorParallel::Dispatching(Dispatcher {
                     systems: systems
                         .drain()
-                        .map(|(id, system)| (id, system.closure))
+                        .map(|(id, system)| (id, system.name, system.closure))
                         .collect(),
                 });
             }
@@ -121,24 +225,17 @@ where
                     .push(dependant_id);
             }
         }
-        // Cache amount of dependants the system has.
-        let mut systems_without_dependencies: Vec<_> = systems_without_dependencies
-            .drain(..)
-            .map(|id| {
-                (
-                    id,
-                    DependantsLength(systems.get(&id).expect(INVALID_ID).dependants.len()),
-                )
-            })
-            .collect();
-        // Sort independent systems so that those with most dependants are queued first.
-        systems_without_dependencies.sort_by(|(_, a), (_, b)| b.cmp(a));
         // This should be guaranteed by the builder's logic anyway.
         debug_assert!(!systems_without_dependencies.is_empty());
         let (sender, receiver) = crossbeam_channel::unbounded();
-        ExecutorParallel::Scheduling(Scheduler {
+        let mut scheduler = Scheduler {
             systems,
+            archetype_sets,
             archetypes_generation: None,
+            dispatch_allowed,
+            dispatchable: false,
+            scheduling_policy,
+            max_concurrent_systems,
             systems_without_dependencies,
             systems_to_run_now: Vec::new(),
             systems_running: HashSet::new(),
@@ -146,7 +243,10 @@ where
             systems_to_decrement_dependencies: Vec::new(),
             sender,
             receiver,
-        })
+        };
+        // Costs given in the builder make for critical paths even before the first run.
+        scheduler.update_critical_paths();
+        ExecutorParallel::Scheduling(scheduler)
     }
 
     pub fn force_archetype_recalculation(&mut self) {
@@ -1,15 +1,17 @@
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError};
 use hecs::{ArchetypesGeneration, World};
-use rayon::ScopeFifo;
-use std::collections::{HashMap, HashSet};
+use rayon::{prelude::*, ScopeFifo, Yield};
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
 
-use super::{System, DISCONNECTED, INVALID_ID};
-use crate::{ResourceTuple, SystemContext, SystemId};
+use super::{SharedArchetypeSet, System, DISCONNECTED, INVALID_ID};
+use crate::{ResourceTuple, SchedulingPolicy, SystemContext, SystemId};
 
-/// Typed `usize` used to cache the amount of dependants the system associated
-/// with a `SystemId` has; avoids hashmap lookups while sorting.
-#[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
-pub struct DependantsLength(pub usize);
+/// How long the scheduler waits for a system to finish before looking for jobs
+/// of the thread pool to run again, when there were none.
+const IDLE_WAIT: Duration = Duration::from_micros(100);
 
 /// Parallel executor variant, used when systems cannot be proven to be statically disjoint,
 /// or have dependencies.
@@ -18,14 +20,23 @@ where
     Resources: ResourceTuple,
 {
     pub systems: HashMap<SystemId, System<'closures, Resources>>,
+    pub archetype_sets: Vec<SharedArchetypeSet>,
     pub archetypes_generation: Option<ArchetypesGeneration>,
-    pub systems_without_dependencies: Vec<(SystemId, DependantsLength)>,
-    pub systems_to_run_now: Vec<(SystemId, DependantsLength)>,
+    /// Whether neither the scheduling policy nor priorities of systems order them,
+    /// so that they may be dispatched all at once.
+    pub dispatch_allowed: bool,
+    /// Whether all systems are independent and disjoint in the current world's archetypes,
+    /// so that they can be dispatched all at once; updated with the archetype sets.
+    pub dispatchable: bool,
+    pub scheduling_policy: Box<dyn SchedulingPolicy + 'closures>,
+    pub max_concurrent_systems: usize,
+    pub systems_without_dependencies: Vec<SystemId>,
+    pub systems_to_run_now: Vec<SystemId>,
     pub systems_running: HashSet<SystemId>,
-    pub systems_just_finished: Vec<SystemId>,
+    pub systems_just_finished: Vec<(SystemId, Duration)>,
     pub systems_to_decrement_dependencies: Vec<SystemId>,
-    pub sender: Sender<SystemId>,
-    pub receiver: Receiver<SystemId>,
+    pub sender: Sender<(SystemId, Duration)>,
+    pub receiver: Receiver<(SystemId, Duration)>,
 }
 
 impl<'closures, Resources> Scheduler<'closures, Resources>
@@ -33,18 +44,122 @@ where
     Resources: ResourceTuple,
 {
     pub fn run(&mut self, world: &World, wrapped: Resources::Wrapped) {
-        rayon::scope_fifo(|scope| {
-            self.prepare(world);
-            // All systems have been ran if there are no queued or currently running systems.
-            while !(self.systems_to_run_now.is_empty() && self.systems_running.is_empty()) {
-                self.start_all_currently_runnable(scope, world, &wrapped);
-                self.wait_for_and_process_finished();
+        self.update_archetype_sets(world);
+        if self.dispatchable {
+            self.dispatch(world, &wrapped);
+        } else {
+            rayon::scope_fifo(|scope| {
+                self.prepare(world);
+                // All systems have been ran if there are no queued or currently running systems.
+                while !(self.systems_to_run_now.is_empty() && self.systems_running.is_empty()) {
+                    self.start_all_currently_runnable(scope, world, &wrapped);
+                    self.wait_for_and_process_finished();
+                }
+            });
+            debug_assert!(self.systems_to_run_now.is_empty());
+            debug_assert!(self.systems_running.is_empty());
+            debug_assert!(self.systems_just_finished.is_empty());
+            debug_assert!(self.systems_to_decrement_dependencies.is_empty());
+        }
+        self.update_critical_paths();
+    }
+
+    /// Recalculates critical paths of all systems from their average durations.
+    pub fn update_critical_paths(&mut self) {
+        // Dependants are always inserted after their dependencies, so iterating in reverse
+        // insertion order visits all dependants of a system before the system itself.
+        for index in (0..self.systems.len()).rev() {
+            let system = self.systems.get(&SystemId(index)).expect(INVALID_ID);
+            let longest_dependant = system
+                .dependants
+                .iter()
+                .map(|id| self.systems.get(id).expect(INVALID_ID).critical_path)
+                .max()
+                .unwrap_or_default();
+            let duration = system.average_duration.or(system.cost).unwrap_or_default();
+            let critical_path = duration + longest_dependant;
+            self.systems
+                .get_mut(&SystemId(index))
+                .expect(INVALID_ID)
+                .critical_path = critical_path;
+        }
+    }
+
+    /// Updates archetype sets of all systems if archetypes have changed since last run,
+    /// and which systems are skipped; checks if the systems can be dispatched if either changed.
+    fn update_archetype_sets(&mut self, world: &World) {
+        let archetypes_changed = Some(world.archetypes_generation()) != self.archetypes_generation;
+        if archetypes_changed {
+            if self.archetypes_generation.is_none() {
+                // The world might be a different one, so archetypes that have been examined
+                // before can't be relied on.
+                for shared in &mut self.archetype_sets {
+                    shared.set.clear();
+                }
+            }
+            self.archetypes_generation = Some(world.archetypes_generation());
+            // Archetypes are never removed, so only the newly added ones need to be examined.
+            for shared in &mut self.archetype_sets {
+                (shared.writer)(world, &mut shared.set);
+                shared.set.mark_written(world);
+            }
+        }
+        // Entities are spawned into and despawned from existing archetypes without changing
+        // the generation, so skipped systems are determined on every run.
+        let archetype_sets = &self.archetype_sets;
+        let mut skipped_changed = false;
+        for system in self.systems.values_mut() {
+            let skipped = system.skip_if_unmatched
+                && !archetype_sets[system.archetype_set].set.has_entities(world);
+            skipped_changed |= skipped != system.skipped;
+            system.skipped = skipped;
+        }
+        if !(archetypes_changed || skipped_changed) {
+            return;
+        }
+        self.dispatchable = self.dispatch_allowed
+            && self.systems_without_dependencies.len() == self.systems.len()
+            && {
+                let systems: Vec<_> = self
+                    .systems
+                    .iter()
+                    .filter(|(_, system)| !system.skipped)
+                    .map(|(id, system)| system.info(*id, archetype_sets))
+                    .collect();
+                systems.len() <= self.max_concurrent_systems
+                    && systems.iter().enumerate().all(|(index, system)| {
+                        systems[index + 1..]
+                            .iter()
+                            .all(|other| system.is_compatible(other))
+                    })
+            };
+    }
+
+    /// Runs all systems at once; only valid if they are `dispatchable`.
+    fn dispatch(&mut self, world: &World, wrapped: &Resources::Wrapped) {
+        self.systems.par_iter_mut().for_each(|(id, system)| {
+            if system.skipped {
+                system.record_duration(Duration::default());
+                return;
             }
+            let duration = {
+                let start = Instant::now();
+                let closure = &mut *system
+                    .closure
+                    .try_lock() // TODO should this be .lock() instead?
+                    .expect("systems should only be ran once per execution");
+                closure(
+                    SystemContext {
+                        system_id: Some(*id),
+                        system_name: Some(system.name),
+                        world,
+                    },
+                    wrapped,
+                );
+                start.elapsed()
+            };
+            system.record_duration(duration);
         });
-        debug_assert!(self.systems_to_run_now.is_empty());
-        debug_assert!(self.systems_running.is_empty());
-        debug_assert!(self.systems_just_finished.is_empty());
-        debug_assert!(self.systems_to_decrement_dependencies.is_empty());
     }
 
     fn prepare(&mut self, world: &World) {
@@ -52,24 +167,14 @@ where
         debug_assert!(self.systems_running.is_empty());
         debug_assert!(self.systems_just_finished.is_empty());
         debug_assert!(self.systems_to_decrement_dependencies.is_empty());
+        self.update_archetype_sets(world);
         // Queue systems that don't have any dependencies to run first.
         self.systems_to_run_now
             .extend(&self.systems_without_dependencies);
-        if Some(world.archetypes_generation()) == self.archetypes_generation {
-            // If archetypes haven't changed since last run, reset dependency counters.
-            for system in self.systems.values_mut() {
-                debug_assert!(system.unsatisfied_dependencies == 0);
-                system.unsatisfied_dependencies = system.dependencies;
-            }
-        } else {
-            // If archetypes have changed, recalculate archetype sets for all systems,
-            // and reset dependency counters.
-            self.archetypes_generation = Some(world.archetypes_generation());
-            for system in self.systems.values_mut() {
-                (system.archetype_writer)(world, &mut system.archetype_set);
-                debug_assert!(system.unsatisfied_dependencies == 0);
-                system.unsatisfied_dependencies = system.dependencies;
-            }
+        // Reset dependency counters.
+        for system in self.systems.values_mut() {
+            debug_assert!(system.unsatisfied_dependencies == 0);
+            system.unsatisfied_dependencies = system.dependencies;
         }
     }
 
@@ -83,125 +188,181 @@ where
         Resources::BorrowTuple: Send,
         Resources::Wrapped: Send + Sync,
     {
-        for (id, _) in &self.systems_to_run_now {
+        for id in &self.systems_to_run_now {
+            if self.systems.get(id).expect(INVALID_ID).skipped {
+                // Skipped systems finish immediately, without blocking other systems.
+                self.systems_running.insert(*id);
+                self.sender
+                    .send((*id, Duration::default()))
+                    .expect(DISCONNECTED);
+            }
+        }
+        // Let the scheduling policy choose which of the other queued systems to try starting,
+        // and in which order.
+        let systems = &self.systems;
+        let archetype_sets = &self.archetype_sets;
+        let mut admitted: Vec<_> = self
+            .systems_to_run_now
+            .iter()
+            .map(|id| (id, systems.get(id).expect(INVALID_ID)))
+            .filter(|(_, system)| !system.skipped)
+            .map(|(id, system)| system.info(*id, archetype_sets))
+            .collect();
+        let any_ready = !admitted.is_empty();
+        self.scheduling_policy.admit(&mut admitted);
+        if any_ready && admitted.is_empty() && self.systems_running.is_empty() {
+            panic!(
+                "the scheduling policy admitted none of the ready systems while none are running"
+            );
+        }
+        let mut running_count = self
+            .systems_running
+            .iter()
+            .filter(|id| !systems.get(id).expect(INVALID_ID).skipped)
+            .count();
+        for info in &admitted {
+            if running_count == self.max_concurrent_systems {
+                break;
+            }
             // Check if a queued system can run concurrently with
             // other systems already running.
-            if self.can_start_now(*id) {
+            let can_start_now = self.systems_running.iter().all(|id| {
+                let other = systems.get(id).expect(INVALID_ID);
+                other.skipped || info.is_compatible(&other.info(*id, archetype_sets))
+            });
+            if can_start_now {
+                let id = info.id();
                 // Add it to the currently running systems set.
-                self.systems_running.insert(*id);
+                self.systems_running.insert(id);
+                running_count += 1;
                 // Pointers and data to send over to a worker thread.
-                let system = self.systems.get_mut(id).expect(INVALID_ID).closure.clone();
+                let system = systems.get(&id).expect(INVALID_ID);
+                let (name, system) = (system.name, system.closure.clone());
                 let sender = self.sender.clone();
-                let id = *id;
                 scope.spawn_fifo(move |_| {
+                    let _finished = Finished {
+                        id,
+                        start: Instant::now(),
+                        sender,
+                    };
                     let system = &mut *system
                         .try_lock() // TODO should this be .lock() instead?
                         .expect("systems should only be ran once per execution");
                     system(
                         SystemContext {
                             system_id: Some(id),
+                            system_name: Some(name),
                             world,
                         },
                         wrapped,
                     );
-                    // Notify dispatching thread than this system has finished running.
-                    sender.send(id).expect(DISCONNECTED);
                 });
             }
         }
-        {
-            // Remove newly running systems from systems-to-run-now set.
-            // TODO replace with `.drain_filter()` once stable
-            //  https://github.com/rust-lang/rust/issues/43244
-            let mut i = 0;
-            while i != self.systems_to_run_now.len() {
-                if self.systems_running.contains(&self.systems_to_run_now[i].0) {
-                    self.systems_to_run_now.remove(i);
-                } else {
-                    i += 1;
-                }
-            }
-        }
-    }
-
-    fn can_start_now(&self, id: SystemId) -> bool {
-        let system = self.systems.get(&id).expect(INVALID_ID);
-        for id in &self.systems_running {
-            let running_system = self.systems.get(id).expect(INVALID_ID);
-            // A system can't run if the resources it needs are already borrowed incompatibly.
-            if !system
-                .resource_set
-                .is_compatible(&running_system.resource_set)
-            {
-                return false;
-            }
-            // A system can't run if it could borrow incompatibly any components.
-            // This can only happen if the system could incompatibly access the same components
-            // from the same archetype that another system may be using.
-            if !system
-                .component_set
-                .is_compatible(&running_system.component_set)
-                && !system
-                    .archetype_set
-                    .is_compatible(&running_system.archetype_set)
-            {
-                return false;
-            }
-        }
-        true
+        // Remove newly running systems from systems-to-run-now set.
+        let systems_running = &self.systems_running;
+        self.systems_to_run_now
+            .retain(|id| !systems_running.contains(id));
     }
 
     fn wait_for_and_process_finished(&mut self) {
         // Wait until at least one system is finished.
-        self.systems_just_finished
-            .push(self.receiver.recv().expect(DISCONNECTED));
+        let finished = self.wait_for_finished();
+        self.systems_just_finished.push(finished);
         // Handle any other systems that may have finished.
         self.systems_just_finished.extend(self.receiver.try_iter());
         // Remove finished systems from set of running systems.
-        for id in &self.systems_just_finished {
+        for (id, _) in &self.systems_just_finished {
             self.systems_running.remove(id);
         }
-        // Gather dependants of finished systems.
-        for finished in &self.systems_just_finished {
-            for dependant in &self.systems.get(finished).expect(INVALID_ID).dependants {
+        // Record durations and gather dependants of finished systems.
+        for (finished, duration) in self.systems_just_finished.drain(..) {
+            let system = self.systems.get_mut(&finished).expect(INVALID_ID);
+            system.record_duration(duration);
+            for dependant in &system.dependants {
                 self.systems_to_decrement_dependencies.push(*dependant);
             }
         }
-        self.systems_just_finished.clear();
         // Figure out which of the gathered dependants have had all their dependencies
         // satisfied and queue them to run.
         for id in self.systems_to_decrement_dependencies.drain(..) {
-            let system = &mut self.systems.get_mut(&id).expect(INVALID_ID);
-            let dependants = DependantsLength(system.dependants.len());
-            let unsatisfied_dependencies = &mut system.unsatisfied_dependencies;
+            let unsatisfied_dependencies = &mut self
+                .systems
+                .get_mut(&id)
+                .expect(INVALID_ID)
+                .unsatisfied_dependencies;
             *unsatisfied_dependencies -= 1;
             if *unsatisfied_dependencies == 0 {
-                self.systems_to_run_now.push((id, dependants));
+                self.systems_to_run_now.push(id);
+            }
+        }
+    }
+
+    /// Waits until a system is finished, without blocking the worker thread: until then,
+    /// the thread runs other jobs of the thread pool, such as the systems just started,
+    /// systems of nested executors, or jobs spawned by systems, which may otherwise have
+    /// no thread left to run on in a small pool.
+    fn wait_for_finished(&self) -> (SystemId, Duration) {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(finished) => return finished,
+                Err(TryRecvError::Disconnected) => panic!("{}", DISCONNECTED),
+                Err(TryRecvError::Empty) => (),
+            }
+            match rayon::yield_now() {
+                Some(Yield::Executed) => (),
+                Some(Yield::Idle) => match self.receiver.recv_timeout(IDLE_WAIT) {
+                    Ok(finished) => return finished,
+                    Err(RecvTimeoutError::Disconnected) => panic!("{}", DISCONNECTED),
+                    Err(RecvTimeoutError::Timeout) => (),
+                },
+                // Not on a thread of a pool, so there are no jobs to run.
+                None => return self.receiver.recv().expect(DISCONNECTED),
             }
         }
-        // Sort queued systems so that those with most dependants run first.
-        self.systems_to_run_now.sort_by(|(_, a), (_, b)| b.cmp(a));
     }
 
     #[cfg(test)]
     fn wait_for_one_finished(&mut self) {
-        self.systems_just_finished
-            .push(self.receiver.recv().expect(DISCONNECTED));
+        let finished = self.wait_for_finished();
+        self.systems_just_finished.push(finished);
+    }
+}
+
+/// Notifies the scheduler that a system has finished running when dropped, including when
+/// the system panics: the scope then propagates the panic instead of the scheduler waiting
+/// for the system forever.
+struct Finished {
+    id: SystemId,
+    start: Instant,
+    sender: Sender<(SystemId, Duration)>,
+}
+
+impl Drop for Finished {
+    fn drop(&mut self) {
+        // Panicking here while unwinding would abort; the receiver outlives the scope anyway.
+        let _ = self.sender.send((self.id, self.start.elapsed()));
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::ExecutorParallel;
+    use super::{super::ExecutorParallel, Scheduler};
     use crate::{
         resource::{AtomicBorrow, ResourceWrap},
-        Executor, QueryMarker, SystemContext,
+        CriticalPath, DependantsLength, Executor, QueryMarker, SchedulingPolicy, SystemContext,
+        SystemId,
     };
     use hecs::World;
     use rayon::{ScopeFifo, ThreadPoolBuilder};
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        time::{Duration, Instant},
+    };
 
     struct A(usize);
     struct B(usize);
+    #[allow(dead_code)]
     struct C(usize);
 
     fn dummy_system(_: SystemContext, _: (), _: ()) {}
@@ -556,4 +717,246 @@ mod tests {
             assert_eq!(b.0, 4);
         }*/
     }
+
+    #[test]
+    fn queries_disjoint_by_components() {
+        let mut world = World::new();
+        world.spawn_batch((0..10).map(|_| (A(0), B(0))));
+        world.spawn_batch((0..10).map(|_| (A(0), C(0))));
+        let mut executor = ExecutorParallel::<()>::build(
+            Executor::builder()
+                .system(|ctx, _: (), q: QueryMarker<(&mut A, &B)>| {
+                    for (_, (a, _)) in ctx.query(q).iter() {
+                        a.0 += 1;
+                    }
+                })
+                .system(
+                    |ctx, _: (), (q0, q1): (QueryMarker<&B>, QueryMarker<(&mut A, &C)>)| {
+                        for (_, _) in ctx.query(q0).iter() {}
+                        for (_, (a, _)) in ctx.query(q1).iter() {
+                            a.0 += 1;
+                        }
+                    },
+                ),
+        )
+        .unwrap_to_scheduler();
+        let wrapped = ();
+        local_pool_scope_fifo(|scope| {
+            executor.prepare(&world);
+            executor.start_all_currently_runnable(scope, &world, &wrapped);
+            assert_eq!(executor.systems_running.len(), 2);
+            executor.wait_for_one_finished();
+            executor.wait_for_and_process_finished();
+            assert!(executor.systems_running.is_empty());
+            assert!(executor.systems_to_run_now.is_empty());
+        });
+        for (_, a) in world.query::<&A>().iter() {
+            assert_eq!(a.0, 1);
+        }
+    }
+
+    #[test]
+    fn dispatchable_by_archetypes() {
+        let mut world = World::new();
+        world.spawn_batch((0..10).map(|_| (A(0), B(0))));
+        world.spawn_batch((0..10).map(|_| (B(0), C(0))));
+        let mut executor = ExecutorParallel::<()>::build(
+            Executor::builder()
+                .system(|ctx, _: (), q: QueryMarker<(&A, &mut B)>| {
+                    for (_, (_, b)) in ctx.query(q).iter() {
+                        b.0 += 1;
+                    }
+                })
+                .system(|ctx, _: (), q: QueryMarker<(&mut B, &C)>| {
+                    for (_, (b, _)) in ctx.query(q).iter() {
+                        b.0 += 1;
+                    }
+                }),
+        )
+        .unwrap_to_scheduler();
+        executor.run(&world, ());
+        assert!(executor.dispatchable);
+        executor.run(&world, ());
+        for (_, b) in world.query::<&B>().iter() {
+            assert_eq!(b.0, 2);
+        }
+        world.spawn_batch((0..10).map(|_| (A(0), B(0), C(0))));
+        executor.run(&world, ());
+        assert!(!executor.dispatchable);
+        for (_, (b, a, c)) in world.query::<(&B, Option<&A>, Option<&C>)>().iter() {
+            let expected = if a.is_some() && c.is_some() { 2 } else { 3 };
+            assert_eq!(b.0, expected);
+        }
+    }
+
+    #[test]
+    fn not_dispatched_if_ordered() {
+        let world = World::new();
+        let build = |builder: crate::ExecutorBuilder<'static, ()>| {
+            let mut executor = ExecutorParallel::build(builder).unwrap_to_scheduler();
+            executor.run(&world, ());
+            assert!(!executor.dispatchable);
+        };
+        let builder = || {
+            Executor::builder()
+                .system(dummy_system)
+                .system(dummy_system)
+                .system(dummy_system)
+        };
+        build(builder().priority(1));
+        build(builder().scheduling_policy(CriticalPath));
+        build(builder().max_concurrent_systems(2));
+    }
+
+    #[test]
+    fn archetype_sets_shared() {
+        let mut world = World::new();
+        world.spawn((A(0), B(0)));
+        let mut executor = ExecutorParallel::<(A,)>::build(
+            Executor::builder()
+                .system(|_, _: &mut A, _: QueryMarker<&mut B>| {})
+                .system(|_, _: &mut A, _: QueryMarker<&mut B>| {})
+                .system(|_, _: &mut A, _: QueryMarker<&B>| {}),
+        )
+        .unwrap_to_scheduler();
+        assert_eq!(executor.archetype_sets.len(), 2);
+        executor.update_archetype_sets(&world);
+        let bits = |executor: &Scheduler<(A,)>| {
+            executor
+                .archetype_sets
+                .iter()
+                .map(|shared| {
+                    shared.set.mutable.count_ones(..) + shared.set.immutable.count_ones(..)
+                })
+                .sum::<usize>()
+        };
+        assert_eq!(bits(&executor), 2);
+        world.spawn((B(0), C(0)));
+        executor.update_archetype_sets(&world);
+        assert_eq!(bits(&executor), 4);
+        executor.archetypes_generation = None;
+        executor.update_archetype_sets(&world);
+        assert_eq!(bits(&executor), 4);
+    }
+
+    #[test]
+    fn critical_path() {
+        fn slow_system(_: SystemContext, _: (), _: ()) {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let world = World::new();
+        let mut executor = ExecutorParallel::<()>::build(
+            Executor::builder()
+                .system_with_handle(slow_system, 0)
+                .system_with_deps(slow_system, vec![0])
+                .system_with_handle(dummy_system, 1)
+                .system_with_deps(dummy_system, vec![1])
+                .system_with_deps(dummy_system, vec![1]),
+        )
+        .unwrap_to_scheduler();
+        executor.run(&world, ());
+        assert!(executor.systems[&SystemId(0)].critical_path >= Duration::from_millis(10));
+        let first = |executor: &Scheduler<()>, policy: &mut dyn SchedulingPolicy| {
+            let mut ready: Vec<_> = executor
+                .systems_without_dependencies
+                .iter()
+                .map(|id| executor.systems[id].info(*id, &executor.archetype_sets))
+                .collect();
+            policy.admit(&mut ready);
+            ready[0].id()
+        };
+        assert_eq!(first(&executor, &mut CriticalPath), SystemId(0));
+        assert_eq!(first(&executor, &mut DependantsLength), SystemId(2));
+    }
+
+    #[test]
+    fn critical_path_costs() {
+        let executor = ExecutorParallel::<()>::build(
+            Executor::builder()
+                .system_with_handle(dummy_system, 0)
+                .cost(Duration::from_millis(2))
+                .system_with_deps(dummy_system, vec![0])
+                .cost(Duration::from_millis(3))
+                .system(dummy_system)
+                .cost(Duration::from_millis(4))
+                .priority(-1),
+        )
+        .unwrap_to_scheduler();
+        let critical_path = |executor: &Scheduler<()>, id| executor.systems[&id].critical_path;
+        assert_eq!(
+            critical_path(&executor, SystemId(0)),
+            Duration::from_millis(5)
+        );
+        assert_eq!(
+            critical_path(&executor, SystemId(2)),
+            Duration::from_millis(4)
+        );
+        let mut ready: Vec<_> = executor
+            .systems_without_dependencies
+            .iter()
+            .map(|id| executor.systems[id].info(*id, &executor.archetype_sets))
+            .collect();
+        CriticalPath.admit(&mut ready);
+        assert_eq!(ready[0].id(), SystemId(0));
+        ready.reverse();
+        DependantsLength.admit(&mut ready);
+        assert_eq!(ready[0].id(), SystemId(0));
+    }
+
+    #[test]
+    fn nested_single_thread() {
+        let world = World::new();
+        let mut a = A(0);
+        let nested = Executor::<(A,)>::builder()
+            .system_with_handle(|_, a: &mut A, _: ()| a.0 += 1, 0)
+            .system_with_deps(|_, a: &mut A, _: ()| a.0 += 1, vec![0])
+            .build();
+        let mut executor = Executor::<(A,)>::builder()
+            .system_with_handle(|_, a: &mut A, _: ()| a.0 += 1, 0)
+            .executor_with_handle_and_deps(nested, 1, vec![0])
+            .system_with_deps(|_, a: &mut A, _: ()| a.0 += 1, vec![1])
+            .build();
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        pool.install(|| executor.run(&world, &mut a));
+        assert_eq!(a.0, 4);
+    }
+
+    #[test]
+    fn nested_concurrent_two_threads() {
+        // Returns `true` if the other system has also started before a generous deadline.
+        fn rendezvous(own: &AtomicBool, other: &AtomicBool) -> bool {
+            own.store(true, Ordering::SeqCst);
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !other.load(Ordering::SeqCst) {
+                if Instant::now() > deadline {
+                    return false;
+                }
+                std::thread::yield_now();
+            }
+            true
+        }
+        let world = World::new();
+        let (started_0, started_1) = (AtomicBool::new(false), AtomicBool::new(false));
+        let (met_0, met_1) = (AtomicBool::new(false), AtomicBool::new(false));
+        let mut b = B(0);
+        let nested = Executor::<(B,)>::builder()
+            .system_with_handle(
+                |_, _: (), _: ()| met_0.store(rendezvous(&started_0, &started_1), Ordering::SeqCst),
+                0,
+            )
+            .system(|_, _: (), _: ()| {
+                met_1.store(rendezvous(&started_1, &started_0), Ordering::SeqCst)
+            })
+            .system_with_deps(|_, b: &mut B, _: ()| b.0 += 1, vec![0])
+            .build();
+        let mut executor = Executor::<(B,)>::builder()
+            .executor_with_handle(nested, 0)
+            .system_with_deps(|_, b: &mut B, _: ()| b.0 += 1, vec![0])
+            .build();
+        let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        pool.install(|| executor.run(&world, &mut b));
+        assert!(met_0.load(Ordering::SeqCst));
+        assert!(met_1.load(Ordering::SeqCst));
+        assert_eq!(b.0, 2);
+    }
 }
@@ -0,0 +1,76 @@
+use crate::{ExecutorBuilder, ResourceTuple};
+
+use super::builder::DummyHandle;
+
+/// A reusable group of systems that can be added to an
+/// [`ExecutorBuilder`](struct.ExecutorBuilder.html) in one go, with
+/// [`ExecutorBuilder::plugin()`](struct.ExecutorBuilder.html#method.plugin).
+///
+/// Implemented for all closures and functions that take a builder and return it.
+/// Plugins can only use handles of the same type as the builder they're added to;
+/// to compose builders with handles made separately, see
+/// [`ExecutorBuilder::merge()`](struct.ExecutorBuilder.html#method.merge).
+///
+/// # Example
+/// ```rust
+/// # use yaks::{Executor, ExecutorBuilder, Plugin};
+/// # let world = hecs::World::new();
+/// struct Time(f32);
+/// struct Speed(f32);
+///
+/// struct Physics {
+///     gravity: f32,
+/// }
+///
+/// impl<'closures, Handle> Plugin<'closures, (Time, Speed), Handle> for Physics
+/// where
+///     Handle: Eq + std::hash::Hash,
+/// {
+///     fn build(
+///         self,
+///         builder: ExecutorBuilder<'closures, (Time, Speed), Handle>,
+///     ) -> ExecutorBuilder<'closures, (Time, Speed), Handle> {
+///         let gravity = self.gravity;
+///         builder.system(move |_context, (time, speed): (&Time, &mut Speed), _queries: ()| {
+///             speed.0 += gravity * time.0;
+///         })
+///     }
+/// }
+///
+/// fn clock(
+///     builder: ExecutorBuilder<(Time, Speed)>,
+/// ) -> ExecutorBuilder<(Time, Speed)> {
+///     builder.system(|_context, time: &mut Time, _queries: ()| time.0 = 0.5)
+/// }
+///
+/// let mut executor = Executor::<(Time, Speed)>::builder()
+///     .plugin(clock)
+///     .plugin(Physics { gravity: 2.0 })
+///     .build();
+/// executor.run(&world, (&mut Time(0.0), &mut Speed(0.0)));
+/// ```
+pub trait Plugin<'closures, Resources, Handle = DummyHandle>
+where
+    Resources: ResourceTuple,
+{
+    /// Adds the plugin's systems to the builder.
+    fn build(
+        self,
+        builder: ExecutorBuilder<'closures, Resources, Handle>,
+    ) -> ExecutorBuilder<'closures, Resources, Handle>;
+}
+
+impl<'closures, Resources, Handle, F0> Plugin<'closures, Resources, Handle> for F0
+where
+    Resources: ResourceTuple + 'closures,
+    F0: FnOnce(
+        ExecutorBuilder<'closures, Resources, Handle>,
+    ) -> ExecutorBuilder<'closures, Resources, Handle>,
+{
+    fn build(
+        self,
+        builder: ExecutorBuilder<'closures, Resources, Handle>,
+    ) -> ExecutorBuilder<'closures, Resources, Handle> {
+        self(builder)
+    }
+}
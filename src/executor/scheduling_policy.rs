@@ -0,0 +1,163 @@
+use std::{cmp::Reverse, time::Duration};
+
+use super::SystemId;
+use crate::{ArchetypeSet, BorrowSet};
+
+/// Decides which of the systems that are ready to run an executor tries to start,
+/// and in which order; set with
+/// [`ExecutorBuilder::scheduling_policy()`](struct.ExecutorBuilder.html#method.scheduling_policy).
+///
+/// Out of the box, there are [`DependantsLength`](struct.DependantsLength.html), the default,
+/// and [`CriticalPath`](struct.CriticalPath.html).
+///
+/// Has no effect if the default `parallel` feature is disabled, or if the executor's systems
+/// have no dependencies and are disjoint, in which case they are all started at once.
+///
+/// # Example
+/// ```rust
+/// # use yaks::{Executor, SchedulingPolicy, SystemInfo};
+/// # let world = hecs::World::new();
+/// /// Starts the input system before any other, and delays systems that took longer
+/// /// than a millisecond until nothing else is ready.
+/// struct InputFirst;
+///
+/// impl SchedulingPolicy for InputFirst {
+///     fn admit(&mut self, ready: &mut Vec<SystemInfo>) {
+///         ready.sort_by_key(|system| system.name() != "input");
+///         let short = |system: &SystemInfo| {
+///             system.last_duration() < Some(std::time::Duration::from_millis(1))
+///         };
+///         if ready.iter().any(short) {
+///             ready.retain(short);
+///         }
+///     }
+/// }
+///
+/// let mut executor = Executor::<()>::builder()
+///     .system(|_context, _resources: (), _queries: ()| {})
+///     .system(|_context, _resources: (), _queries: ()| {})
+///     .named("input")
+///     .scheduling_policy(InputFirst)
+///     .build();
+/// executor.run(&world, ());
+/// ```
+pub trait SchedulingPolicy: Send + Sync {
+    /// Reorders the systems that are ready to run so that the ones to start first come first,
+    /// and removes the ones that shouldn't be started yet.
+    ///
+    /// The executor tries to start the remaining systems in order, leaving out those that
+    /// can't run at the same time as the systems already running. Those, as well as removed
+    /// ones, are offered again after any running system finishes.
+    ///
+    /// # Panics
+    /// The executor will panic if no systems are left while none are running.
+    fn admit(&mut self, ready: &mut Vec<SystemInfo>);
+}
+
+/// Default [`SchedulingPolicy`](trait.SchedulingPolicy.html): starts systems with the highest
+/// priority first, then those with the most direct dependants.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct DependantsLength;
+
+impl SchedulingPolicy for DependantsLength {
+    fn admit(&mut self, ready: &mut Vec<SystemInfo>) {
+        ready.sort_by_key(|system| Reverse((system.priority(), system.dependants())));
+    }
+}
+
+/// [`SchedulingPolicy`](trait.SchedulingPolicy.html) that starts systems with the highest
+/// priority first, then those with the longest chains of dependants ahead of them, weighted
+/// by how long the systems took to run
+/// (see [`SystemInfo::critical_path()`](struct.SystemInfo.html#method.critical_path)),
+/// falling back to the amount of direct dependants.
+///
+/// This reduces the time it takes to run executors where a long chain of dependent
+/// systems would otherwise be started late, behind many short independent ones.
+/// Since durations of systems are averaged over several runs, the order settles after
+/// a few runs and adapts as the workload changes.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct CriticalPath;
+
+impl SchedulingPolicy for CriticalPath {
+    fn admit(&mut self, ready: &mut Vec<SystemInfo>) {
+        ready.sort_by_key(|system| {
+            Reverse((
+                system.priority(),
+                system.critical_path(),
+                system.dependants(),
+            ))
+        });
+    }
+}
+
+/// Scheduling metadata of a system that is ready to run, given to
+/// a [`SchedulingPolicy`](trait.SchedulingPolicy.html).
+pub struct SystemInfo<'a> {
+    pub(crate) id: SystemId,
+    pub(crate) name: &'static str,
+    pub(crate) dependants: usize,
+    pub(crate) priority: i32,
+    pub(crate) cost: Option<Duration>,
+    pub(crate) last_duration: Option<Duration>,
+    pub(crate) average_duration: Option<Duration>,
+    pub(crate) critical_path: Duration,
+    pub(crate) resource_set: &'a BorrowSet,
+    pub(crate) component_set: &'a BorrowSet,
+    pub(crate) archetype_set: &'a ArchetypeSet,
+}
+
+impl<'a> SystemInfo<'a> {
+    /// Returns a debug-printable `SystemId` of the system, same as
+    /// [`SystemContext::id()`](struct.SystemContext.html#method.id).
+    pub fn id(&self) -> SystemId {
+        self.id
+    }
+
+    /// Returns the name of the system; see
+    /// [`ExecutorBuilder::named()`](struct.ExecutorBuilder.html#method.named).
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the amount of systems that directly depend on the system.
+    pub fn dependants(&self) -> usize {
+        self.dependants
+    }
+
+    /// Returns the priority of the system; see
+    /// [`ExecutorBuilder::priority()`](struct.ExecutorBuilder.html#method.priority).
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Returns the expected duration of the system, if given; see
+    /// [`ExecutorBuilder::cost()`](struct.ExecutorBuilder.html#method.cost).
+    pub fn cost(&self) -> Option<Duration> {
+        self.cost
+    }
+
+    /// Returns how long the system took to run the last time it was ran, if it was.
+    pub fn last_duration(&self) -> Option<Duration> {
+        self.last_duration
+    }
+
+    /// Returns the exponential moving average of the system's durations, if it was ran.
+    pub fn average_duration(&self) -> Option<Duration> {
+        self.average_duration
+    }
+
+    /// Returns the longest sum of average durations (or costs, for systems that haven't
+    /// been ran yet) along a chain of systems that depend on each other, starting with
+    /// this system; updated after every run of the executor.
+    pub fn critical_path(&self) -> Duration {
+        self.critical_path
+    }
+
+    /// Returns `true` if the systems may run at the same time: the resources they borrow
+    /// are compatible, and so are the components of archetypes of the world they may query.
+    pub fn is_compatible(&self, other: &SystemInfo) -> bool {
+        self.resource_set.is_compatible(other.resource_set)
+            && (self.component_set.is_compatible(other.component_set)
+                || self.archetype_set.is_compatible(other.archetype_set))
+    }
+}
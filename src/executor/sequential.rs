@@ -1,13 +1,27 @@
-use hecs::World;
+use hecs::{ArchetypesGeneration, World};
+use std::sync::Arc;
 
-use super::SystemClosure;
-use crate::{ExecutorBuilder, ResourceTuple, SystemContext, SystemId};
+use super::{ArchetypeWriter, SystemClosure};
+use crate::{ArchetypeSet, ExecutorBuilder, ResourceTuple, SystemContext, SystemId};
+
+struct System<'closure, Resources>
+where
+    Resources: ResourceTuple,
+{
+    id: SystemId,
+    name: &'static str,
+    closure: Box<SystemClosure<'closure, Resources::Wrapped>>,
+    /// Archetype set of a system that is skipped when none of the archetypes it matches
+    /// contain entities, and the function that updates it.
+    archetype_set: Option<(ArchetypeSet, Box<ArchetypeWriter>)>,
+}
 
 pub struct ExecutorSequential<'closures, Resources>
 where
     Resources: ResourceTuple,
 {
-    systems: Vec<(SystemId, Box<SystemClosure<'closures, Resources::Wrapped>>)>,
+    systems: Vec<System<'closures, Resources>>,
+    archetypes_generation: Option<ArchetypesGeneration>,
 }
 
 impl<'closures, Resources> ExecutorSequential<'closures, Resources>
@@ -18,19 +32,67 @@ where
         let ExecutorBuilder { mut systems, .. } = builder;
         let mut systems: Vec<_> = systems
             .drain()
-            .map(|(id, system)| (id, system.closure))
+            .map(|(id, system)| {
+                let types = &system.component_type_set;
+                let archetype_set = if system.skip_if_unmatched && !types.is_empty() {
+                    let components: Arc<[_]> =
+                        types.immutable.union(&types.mutable).copied().collect();
+                    Some((ArchetypeSet::new(components), system.archetype_writer))
+                } else {
+                    None
+                };
+                System {
+                    id,
+                    name: system.name,
+                    closure: system.closure,
+                    archetype_set,
+                }
+            })
             .collect();
-        systems.sort_by(|(a, _), (b, _)| a.cmp(b));
-        ExecutorSequential { systems }
+        systems.sort_by_key(|system| system.id);
+        ExecutorSequential {
+            systems,
+            archetypes_generation: None,
+        }
     }
 
-    pub fn force_archetype_recalculation(&mut self) {}
+    pub fn force_archetype_recalculation(&mut self) {
+        self.archetypes_generation = None;
+    }
+
+    /// Updates archetype sets of systems that may be skipped, if archetypes have changed
+    /// since last run.
+    fn update_archetype_sets(&mut self, world: &World) {
+        if Some(world.archetypes_generation()) == self.archetypes_generation {
+            return;
+        }
+        let recalculate = self.archetypes_generation.is_none();
+        self.archetypes_generation = Some(world.archetypes_generation());
+        for (set, writer) in self
+            .systems
+            .iter_mut()
+            .filter_map(|system| system.archetype_set.as_mut())
+        {
+            if recalculate {
+                set.clear();
+            }
+            writer(world, set);
+            set.mark_written(world);
+        }
+    }
 
     pub fn run(&mut self, world: &World, wrapped: Resources::Wrapped) {
-        for (id, closure) in &mut self.systems {
-            closure(
+        self.update_archetype_sets(world);
+        for system in &mut self.systems {
+            if let Some((set, _)) = &system.archetype_set {
+                if !set.has_entities(world) {
+                    continue;
+                }
+            }
+            (system.closure)(
                 SystemContext {
-                    system_id: Some(*id),
+                    system_id: Some(system.id),
+                    system_name: Some(system.name),
                     world,
                 },
                 &wrapped,
@@ -0,0 +1,297 @@
+use hecs::World;
+use std::marker::PhantomData;
+
+use crate::{
+    query_bundle::QueryExt, ArchetypeSet, BorrowSet, BorrowTypeSet, Fetch, QueryMarker,
+    SystemContext,
+};
+
+/// Marker for the `SystemParam` implementation of `SystemContext`.
+pub struct ContextParam;
+
+/// Marker for the `SystemParam` implementation of query markers.
+pub struct QueryParam;
+
+/// Marker for the `SystemParam` implementation of resources, wrapping the marker of `Fetch`.
+pub struct ResourceParam<M0>(PhantomData<M0>);
+
+/// Specifies how a single parameter of a flexible system is obtained.
+pub trait SystemParam<'a, T, M0>: Sized {
+    /// Borrows or creates the parameter.
+    fn get(context: &SystemContext<'a>, resources: &'a T) -> Self;
+
+    /// Releases the borrows made by `get()`.
+    ///
+    /// # Safety
+    /// Must only be called once per `get()`, after the parameter is no longer used.
+    unsafe fn release(_resources: &'a T) {}
+
+    /// Records which of the cells are borrowed, and how.
+    fn set_resource_bits(_resource_set: &mut BorrowSet) {}
+
+    /// Records component types the parameter may query.
+    fn insert_component_types(_component_type_set: &mut BorrowTypeSet) {}
+
+    /// Records archetypes the parameter may query.
+    fn set_archetype_bits(_world: &World, _archetype_set: &mut ArchetypeSet) {}
+}
+
+impl<'a, T> SystemParam<'a, T, ContextParam> for SystemContext<'a> {
+    fn get(context: &SystemContext<'a>, _: &'a T) -> Self {
+        SystemContext {
+            system_id: context.system_id,
+            system_name: context.system_name,
+            world: context.world,
+        }
+    }
+}
+
+impl<'a, T, Q0> SystemParam<'a, T, QueryParam> for QueryMarker<Q0>
+where
+    Q0: QueryExt,
+{
+    fn get(_: &SystemContext<'a>, _: &'a T) -> Self {
+        QueryMarker::new()
+    }
+
+    fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
+        Q0::insert_query_component_types(component_type_set);
+    }
+
+    fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
+        Q0::set_archetype_bits(world, archetype_set);
+    }
+}
+
+macro_rules! impl_system_param_queries {
+    ($($letter:ident),*) => {
+        impl<'a, T, $($letter),*> SystemParam<'a, T, QueryParam> for ($(QueryMarker<$letter>,)*)
+        where
+            $($letter: QueryExt,)*
+        {
+            fn get(_: &SystemContext<'a>, _: &'a T) -> Self {
+                ($(QueryMarker::<$letter>::new(),)*)
+            }
+
+            fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
+                $($letter::insert_query_component_types(component_type_set);)*
+            }
+
+            fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
+                $($letter::set_archetype_bits(world, archetype_set);)*
+            }
+        }
+    }
+}
+
+impl_system_param_queries!(A);
+impl_for_tuples!(impl_system_param_queries);
+
+impl<'a, T, M0, F0> SystemParam<'a, T, ResourceParam<M0>> for F0
+where
+    F0: Fetch<'a, T, M0>,
+{
+    fn get(_: &SystemContext<'a>, resources: &'a T) -> Self {
+        F0::fetch(resources)
+    }
+
+    unsafe fn release(resources: &'a T) {
+        F0::release(resources);
+    }
+
+    fn set_resource_bits(resource_set: &mut BorrowSet) {
+        F0::set_resource_bits(resource_set);
+    }
+
+    fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
+        F0::insert_component_types(component_type_set);
+    }
+
+    fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
+        F0::set_archetype_bits(world, archetype_set);
+    }
+}
+
+/// Specifies how all parameters of a flexible system are obtained; implemented for tuples
+/// of `SystemParam`.
+pub trait SystemParamSet<'a, T, M0>: Sized {
+    /// Borrows or creates the parameters.
+    fn get(context: &SystemContext<'a>, resources: &'a T) -> Self;
+
+    /// Releases the borrows made by `get()`.
+    ///
+    /// # Safety
+    /// Must only be called once per `get()`, after the parameters are no longer used.
+    unsafe fn release(resources: &'a T);
+
+    /// Records which of the cells are borrowed, and how.
+    fn set_resource_bits(resource_set: &mut BorrowSet);
+
+    /// Records component types the parameters may query.
+    fn insert_component_types(component_type_set: &mut BorrowTypeSet);
+
+    /// Records archetypes the parameters may query.
+    fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet);
+}
+
+/// Calls a function or a closure with a tuple of its arguments.
+pub trait SystemFn<Params> {
+    /// Calls the function with the unpacked arguments.
+    fn call(&mut self, params: Params);
+}
+
+impl<'a, T> SystemParamSet<'a, T, ()> for () {
+    fn get(_: &SystemContext<'a>, _: &'a T) -> Self {}
+
+    unsafe fn release(_: &'a T) {}
+
+    fn set_resource_bits(_: &mut BorrowSet) {}
+
+    fn insert_component_types(_: &mut BorrowTypeSet) {}
+
+    fn set_archetype_bits(_: &World, _: &mut ArchetypeSet) {}
+}
+
+impl<F0> SystemFn<()> for F0
+where
+    F0: FnMut(),
+{
+    fn call(&mut self, _: ()) {
+        self()
+    }
+}
+
+macro_rules! impl_system_param_set {
+    ($($letter:ident),*) => {
+        paste::item! {
+            impl<'a, T, $($letter, [<M $letter>]),*>
+                SystemParamSet<'a, T, ($([<M $letter>],)*)> for ($($letter,)*)
+            where
+                $($letter: SystemParam<'a, T, [<M $letter>]>,)*
+            {
+                fn get(context: &SystemContext<'a>, resources: &'a T) -> Self {
+                    ($($letter::get(context, resources),)*)
+                }
+
+                unsafe fn release(resources: &'a T) {
+                    $($letter::release(resources);)*
+                }
+
+                fn set_resource_bits(resource_set: &mut BorrowSet) {
+                    $($letter::set_resource_bits(resource_set);)*
+                }
+
+                fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
+                    $($letter::insert_component_types(component_type_set);)*
+                }
+
+                fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
+                    $($letter::set_archetype_bits(world, archetype_set);)*
+                }
+            }
+
+            impl<F0, $($letter),*> SystemFn<($($letter,)*)> for F0
+            where
+                F0: FnMut($($letter),*),
+            {
+                #[allow(non_snake_case)]
+                fn call(&mut self, ($($letter,)*): ($($letter,)*)) {
+                    self($($letter),*)
+                }
+            }
+        }
+    }
+}
+
+impl_system_param_set!(A);
+impl_for_tuples!(impl_system_param_set);
+
+/// Resources argument of systems produced by [`flexible()`](fn.flexible.html); declares
+/// the accesses of all parameters of the wrapped function.
+pub struct SystemParamSource<'a, T, P0, M0> {
+    resources: &'a T,
+    phantom_data: PhantomData<fn() -> (P0, M0)>,
+}
+
+impl<'a, T, P0, M0> Fetch<'a, T, ()> for SystemParamSource<'a, T, P0, M0>
+where
+    P0: SystemParamSet<'a, T, M0>,
+{
+    fn fetch(resources: &'a T) -> Self {
+        SystemParamSource {
+            resources,
+            phantom_data: PhantomData,
+        }
+    }
+
+    unsafe fn release(resources: &'a T) {
+        P0::release(resources);
+    }
+
+    fn set_resource_bits(resource_set: &mut BorrowSet) {
+        P0::set_resource_bits(resource_set);
+    }
+
+    fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
+        P0::insert_component_types(component_type_set);
+    }
+
+    fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
+        P0::set_archetype_bits(world, archetype_set);
+    }
+}
+
+/// Adapts a function or a closure with any number (up to 16) and order of parameters
+/// into a system that can be inserted into an [`ExecutorBuilder`](struct.ExecutorBuilder.html).
+///
+/// Each parameter may be:
+/// - a [`SystemContext`](struct.SystemContext.html),
+/// - a [`QueryMarker`](struct.QueryMarker.html), or a tuple of them,
+/// - anything accepted as the resources argument of a regular system: a reference,
+///   a mutable reference, an `Option` of either, a tuple of those, or a type
+///   deriving `SystemParams`.
+///
+/// A parameter of type `()` is ambiguous and is not accepted; simply omit it.
+///
+/// Neither [`ExecutorBuilder::system()`][system] nor [`System::run()`][run] accept such
+/// functions directly: closures only get their parameter types inferred from a bound with
+/// a fixed signature, so accepting any signature there would require every closure to annotate
+/// all of its parameters. Systems adapted this way can only be used in an executor; to run one
+/// outside of it, use the regular three-argument form with `System::run()`.
+///
+/// [system]: struct.ExecutorBuilder.html#method.system
+/// [run]: trait.System.html#tymethod.run
+///
+/// # Example
+/// ```rust
+/// # use yaks::{QueryMarker, SystemContext, Executor};
+/// # let mut world = hecs::World::new();
+/// # world.spawn((1u32,));
+/// struct Time(f32);
+///
+/// fn tick(time: &mut Time) {
+///     time.0 += 1.0;
+/// }
+///
+/// fn count(query: QueryMarker<&u32>, context: SystemContext, total: &mut usize) {
+///     *total = context.query(query).iter().count();
+/// }
+///
+/// let mut executor = Executor::<(Time, usize)>::builder()
+///     .system(yaks::flexible(tick))
+///     .system(yaks::flexible(count))
+///     .system(|_context, _time: &Time, _queries: ()| {})
+///     .build();
+/// let (mut time, mut total) = (Time(0.0), 0);
+/// executor.run(&world, (&mut time, &mut total));
+/// assert_eq!(total, 1);
+/// ```
+pub fn flexible<'a, T, F0, P0, M0>(
+    mut system: F0,
+) -> impl FnMut(SystemContext<'a>, SystemParamSource<'a, T, P0, M0>, ()) + Send + Sync
+where
+    F0: SystemFn<P0> + Send + Sync,
+    P0: SystemParamSet<'a, T, M0>,
+{
+    move |context, source, _| system.call(P0::get(&context, source.resources))
+}
@@ -30,9 +30,12 @@
 //! # Cargo features
 //!
 //! - `parallel` - enabled by default; can be disabled to force `yaks` to work on a single thread.
-//! Useful for writing the code once, and running it on platforms with or without threading.
+//!   Useful for writing the code once, and running it on platforms with or without threading.
+//! - `macros` - when enabled, provides `#[derive(ResourceBundle)]`, see
+//!   [`ResourceBundle`](trait.ResourceBundle.html), and `#[derive(SystemParams)]`, see
+//!   [`ExecutorBuilder::system()`](struct.ExecutorBuilder.html#method.system).
 //! - `resources-interop` - when enabled, allows `Executor::run()` to also
-//! accept `Resources` struct from the [`resources`] crate in place of resources argument.
+//!   accept `Resources` struct from the [`resources`] crate in place of resources argument.
 //!
 //! [`resources`]: https://crates.io/crates/resources
 //!
@@ -110,10 +113,13 @@
 #[macro_use]
 mod tuple_macro;
 
-#[cfg(feature = "parallel")]
+// Internal, but reachable from `__private` for generated code.
+#[allow(missing_docs)]
 mod access_set;
 mod batch;
 mod executor;
+mod flexible;
+#[allow(missing_docs)]
 mod query_bundle;
 mod query_marker;
 mod resource;
@@ -122,14 +128,31 @@ mod resources_interop;
 mod run;
 mod system_context;
 
-#[cfg(feature = "parallel")]
-use access_set::{ArchetypeSet, BorrowSet, BorrowTypeSet, TypeSet};
+use access_set::{ArchetypeSet, BorrowSet, BorrowTypeSet, ResourceKey, TypeSet};
 use executor::SystemId;
 use query_bundle::QueryBundle;
-use resource::{Fetch, RefExtractor, ResourceTuple};
+use resource::{Fetch, ResourceTuple};
 
-pub use batch::batch;
-pub use executor::{Executor, ExecutorBuilder};
+pub use batch::{batch, batch_entities, batch_with_init, try_batch};
+pub use executor::{
+    CriticalPath, DependantsLength, Executor, ExecutorBuilder, OwningExecutor, Plugin,
+    SchedulingPolicy, SystemInfo,
+};
+pub use flexible::flexible;
 pub use query_marker::QueryMarker;
+pub use resource::{RefExtractor, ResourceBundle, ResourceMap, ResourceSource};
 pub use run::System;
 pub use system_context::SystemContext;
+#[cfg(feature = "macros")]
+pub use yaks_macros::{ResourceBundle, SystemParams};
+
+/// Items used by code generated by `yaks-macros`; not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::{
+        access_set::{ArchetypeSet, BorrowSet, BorrowTypeSet},
+        query_bundle::QueryBundle,
+        resource::{Fetch, FetchField, ResourceTuple, ResourceWrap},
+    };
+    pub use hecs::World;
+}
@@ -1,50 +1,53 @@
 use hecs::{Component, Query, With, Without};
 
-#[cfg(feature = "parallel")]
 use hecs::World;
-#[cfg(feature = "parallel")]
-use std::any::TypeId;
 
 use crate::QueryMarker;
 
-#[cfg(feature = "parallel")]
 use crate::{ArchetypeSet, BorrowTypeSet};
 
 pub trait QueryExt: Query {
-    #[cfg(feature = "parallel")]
     fn insert_component_types(component_type_set: &mut BorrowTypeSet);
 
-    #[cfg(feature = "parallel")]
+    /// Records component types of the query as a whole: its borrows are checked against
+    /// each other, but not against those of other queries in the set, which may be made
+    /// at a different time.
+    fn insert_query_component_types(component_type_set: &mut BorrowTypeSet)
+    where
+        Self: Sized,
+    {
+        let mut query_set = BorrowTypeSet::new();
+        Self::insert_component_types(&mut query_set);
+        component_type_set.extend(&query_set);
+    }
+
     fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet)
     where
         Self: Sized,
     {
-        archetype_set.set_bits_for_query::<Self>(world);
+        let mut component_type_set = BorrowTypeSet::new();
+        Self::insert_component_types(&mut component_type_set);
+        archetype_set.set_bits_for_query::<Self>(world, &component_type_set);
     }
 }
 
 pub trait QueryBundle {
     fn markers() -> Self;
 
-    #[cfg(feature = "parallel")]
     fn insert_component_types(component_type_set: &mut BorrowTypeSet);
 
-    #[cfg(feature = "parallel")]
     fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet);
 }
 
 impl QueryExt for () {
-    #[cfg(feature = "parallel")]
     fn insert_component_types(_: &mut BorrowTypeSet) {}
 }
 
 impl QueryBundle for () {
     fn markers() -> Self {}
 
-    #[cfg(feature = "parallel")]
     fn insert_component_types(_: &mut BorrowTypeSet) {}
 
-    #[cfg(feature = "parallel")]
     fn set_archetype_bits(_: &World, _: &mut ArchetypeSet) {}
 }
 
@@ -52,9 +55,8 @@ impl<C0> QueryExt for &'_ C0
 where
     C0: Component,
 {
-    #[cfg(feature = "parallel")]
     fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
-        component_type_set.immutable.insert(TypeId::of::<C0>());
+        component_type_set.insert_immutable::<C0>();
     }
 }
 
@@ -62,9 +64,8 @@ impl<C0> QueryExt for &'_ mut C0
 where
     C0: Component,
 {
-    #[cfg(feature = "parallel")]
     fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
-        component_type_set.mutable.insert(TypeId::of::<C0>());
+        component_type_set.insert_mutable::<C0>();
     }
 }
 
@@ -72,7 +73,6 @@ impl<Q0> QueryExt for Option<Q0>
 where
     Q0: QueryExt,
 {
-    #[cfg(feature = "parallel")]
     fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
         Q0::insert_component_types(component_type_set);
     }
@@ -83,7 +83,6 @@ where
     C0: Component,
     Q0: QueryExt,
 {
-    #[cfg(feature = "parallel")]
     fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
         Q0::insert_component_types(component_type_set);
     }
@@ -94,7 +93,6 @@ where
     C0: Component,
     Q0: QueryExt,
 {
-    #[cfg(feature = "parallel")]
     fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
         Q0::insert_component_types(component_type_set);
     }
@@ -108,12 +106,10 @@ where
         QueryMarker::new()
     }
 
-    #[cfg(feature = "parallel")]
     fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
-        Q0::insert_component_types(component_type_set);
+        Q0::insert_query_component_types(component_type_set);
     }
 
-    #[cfg(feature = "parallel")]
     fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
         Q0::set_archetype_bits(world, archetype_set);
     }
@@ -123,7 +119,6 @@ impl<Q0> QueryExt for (Q0,)
 where
     Q0: QueryExt,
 {
-    #[cfg(feature = "parallel")]
     fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
         Q0::insert_component_types(component_type_set);
     }
@@ -137,12 +132,10 @@ where
         (QueryMarker::new(),)
     }
 
-    #[cfg(feature = "parallel")]
     fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
-        Q0::insert_component_types(component_type_set);
+        Q0::insert_query_component_types(component_type_set);
     }
 
-    #[cfg(feature = "parallel")]
     fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
         Q0::set_archetype_bits(world, archetype_set);
     }
@@ -154,7 +147,6 @@ macro_rules! impl_query_ext {
         where
             $($letter: QueryExt,)*
         {
-            #[cfg(feature = "parallel")]
             fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
                 $($letter::insert_component_types(component_type_set);)*
             }
@@ -174,12 +166,10 @@ macro_rules! impl_query_bundle {
                 ($(QueryMarker::<$letter>::new(),)*)
             }
 
-            #[cfg(feature = "parallel")]
             fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
-                $($letter::insert_component_types(component_type_set);)*
+                $($letter::insert_query_component_types(component_type_set);)*
             }
 
-            #[cfg(feature = "parallel")]
             fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
                 $($letter::set_archetype_bits(world, archetype_set);)*
             }
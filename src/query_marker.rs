@@ -24,7 +24,7 @@ where
     Q0: Query,
 {
     fn clone(&self) -> Self {
-        QueryMarker::new()
+        *self
     }
 }
 
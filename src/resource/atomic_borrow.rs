@@ -15,15 +15,38 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    ptr,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+};
 
-pub struct AtomicBorrow(AtomicUsize);
+/// Borrow counter that also remembers the name of the system that made the latest borrow,
+/// to name it in panic messages.
+///
+/// The name is tracked on a best-effort basis: it's only read when a borrow fails,
+/// so a concurrent release may occasionally leave it blank.
+pub struct AtomicBorrow(AtomicUsize, AtomicPtr<&'static str>);
 
 impl AtomicBorrow {
-    const UNIQUE_BIT: usize = !(usize::max_value() >> 1);
+    const UNIQUE_BIT: usize = !(usize::MAX >> 1);
 
     pub const fn new() -> Self {
-        Self(AtomicUsize::new(0))
+        Self(AtomicUsize::new(0), AtomicPtr::new(ptr::null_mut()))
+    }
+
+    /// Returns the name of the system holding the borrow; if it's shared,
+    /// the one that borrowed last.
+    pub fn holder(&self) -> Option<&'static str> {
+        let holder = self.1.load(Ordering::Acquire);
+        // SAFETY: the pointer is either null or was made from a `&'static &'static str`.
+        unsafe { holder.as_ref() }.copied()
+    }
+
+    /// Records the name of the system that made the borrow; names are expected
+    /// to be interned, see `RunningSystem`.
+    pub fn set_holder(&self, holder: Option<&'static &'static str>) {
+        let holder = holder.map_or(ptr::null_mut(), |holder| holder as *const _ as *mut _);
+        self.1.store(holder, Ordering::Release);
     }
 
     pub fn is_free(&self) -> bool {
@@ -56,6 +79,9 @@ impl AtomicBorrow {
     }
 
     pub fn release(&self) {
+        if self.0.load(Ordering::Relaxed) == 1 {
+            self.set_holder(None);
+        }
         let value = self.0.fetch_sub(1, Ordering::Release);
         debug_assert!(value != 0, "unbalanced release");
         debug_assert!(
@@ -65,6 +91,7 @@ impl AtomicBorrow {
     }
 
     pub fn release_mut(&self) {
+        self.set_holder(None);
         self.0.store(0, Ordering::Release);
     }
 }
@@ -0,0 +1,84 @@
+use hecs::World;
+
+use super::{RefExtractor, ResourceTuple};
+use crate::Executor;
+
+/// Specifies how a struct with named fields behaves as the generic parameter of an executor;
+/// implemented with `#[derive(ResourceBundle)]`, provided by the `macros` feature.
+///
+/// An executor over a resource bundle, `Executor<SomeBundle>`, behaves the same as one over
+/// a tuple of the bundle's field types: systems fetch the fields by type, so no two fields may
+/// have the same type. The executor is ran with a mutable reference to the bundle. Borrow
+/// conflicts between systems are reported with both the type and the field name.
+///
+/// Bundles are limited to 16 fields; see [`ResourceMap`](struct.ResourceMap.html)
+/// for an alternative without such a limit.
+///
+/// # Example
+/// ```rust
+/// # #[cfg(feature = "macros")]
+/// # {
+/// # use yaks::{Executor, ResourceBundle};
+/// # let world = hecs::World::new();
+/// struct Time(f32);
+/// struct Speed(f32);
+///
+/// #[derive(ResourceBundle)]
+/// struct Resources {
+///     time: Time,
+///     speed: Speed,
+/// }
+///
+/// let mut resources = Resources {
+///     time: Time(0.5),
+///     speed: Speed(0.0),
+/// };
+/// let mut executor = Executor::<Resources>::builder()
+///     .system(|_context, (time, speed): (&Time, &mut Speed), _queries: ()| {
+///         speed.0 += time.0;
+///     })
+///     .build();
+/// executor.run(&world, &mut resources);
+/// assert_eq!(resources.speed.0, 0.5);
+/// # }
+/// ```
+pub trait ResourceBundle: Send + Sync {
+    #[doc(hidden)]
+    type Tuple: ResourceTuple;
+    #[doc(hidden)]
+    const LABELS: &'static [&'static str];
+
+    #[doc(hidden)]
+    fn wrap(
+        &mut self,
+        borrows: &mut <Self::Tuple as ResourceTuple>::BorrowTuple,
+    ) -> <Self::Tuple as ResourceTuple>::Wrapped;
+}
+
+impl<B0> ResourceTuple for B0
+where
+    B0: ResourceBundle,
+{
+    type Wrapped = <B0::Tuple as ResourceTuple>::Wrapped;
+    type BorrowTuple = <B0::Tuple as ResourceTuple>::BorrowTuple;
+    const LENGTH: usize = B0::Tuple::LENGTH;
+
+    fn instantiate_borrows() -> Self::BorrowTuple {
+        B0::Tuple::instantiate_borrows()
+    }
+
+    fn label_cells(wrapped: &mut Self::Wrapped, labels: &[&'static str]) {
+        B0::Tuple::label_cells(wrapped, labels);
+    }
+}
+
+impl<B0> RefExtractor<&mut B0> for B0
+where
+    B0: ResourceBundle,
+{
+    fn extract_and_run(executor: &mut Executor<Self>, world: &World, resources: &mut B0) {
+        let mut wrapped = resources.wrap(&mut executor.borrows);
+        Self::label_cells(&mut wrapped, B0::LABELS);
+        executor.inner.run(world, wrapped);
+    }
+}
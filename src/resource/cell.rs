@@ -1,42 +1,87 @@
-use std::{ptr::NonNull, thread::panicking};
+use std::{any::type_name, ptr::NonNull, thread::panicking};
 
 use super::AtomicBorrow;
+use crate::system_context::RunningSystem;
 
 /// A pointer to a resource, with runtime borrow checking via an `AtomicBorrow`,
 /// accessed through a pointer to a cached one in an executor.
+///
+/// The layout is fixed so that a cell with an erased resource type may be cast back to a cell
+/// with the concrete one (see `ResourceMapCells`).
+#[repr(C)]
 pub struct ResourceCell<R0> {
     cell: NonNull<R0>,
     borrow: NonNull<AtomicBorrow>,
+    label: Option<&'static str>,
 }
 
 impl<R0> ResourceCell<R0> {
-    pub fn new(resource: &mut R0, borrow: &mut AtomicBorrow) -> Self
+    pub fn new(resource: NonNull<R0>, borrow: &mut AtomicBorrow) -> Self
     where
         R0: Send + Sync,
     {
         Self {
-            cell: NonNull::new(resource).expect("pointers to resources should never be null"),
+            cell: resource,
             borrow: NonNull::new(borrow).expect("pointers to AtomicBorrows should never be null"),
+            label: None,
+        }
+    }
+
+    /// Sets the label that will be shown alongside the type name in panic messages.
+    pub fn set_label(&mut self, label: &'static str) {
+        self.label = Some(label);
+    }
+
+    fn name(&self) -> String {
+        match self.label {
+            Some(label) => format!("{} ({})", type_name::<R0>(), label),
+            None => type_name::<R0>().to_string(),
+        }
+    }
+
+    /// Names the system running on the current thread, if any, for panic messages.
+    fn requester() -> String {
+        match RunningSystem::current() {
+            Some(system) => format!(", in system {}", system),
+            None => String::new(),
+        }
+    }
+
+    /// Names the system holding the borrow, if known, for panic messages.
+    fn holder(borrow: &AtomicBorrow) -> String {
+        match borrow.holder() {
+            Some(system) => format!(" by system {}", system),
+            None => String::new(),
         }
     }
 
     pub fn borrow(&self) -> &R0 {
-        assert!(
-            unsafe { self.borrow.as_ref().borrow() },
-            "cannot borrow {} immutably: already borrowed mutably",
-            std::any::type_name::<R0>()
-        );
+        let borrow = unsafe { self.borrow.as_ref() };
+        if !borrow.borrow() {
+            panic!(
+                "cannot borrow {} immutably: already borrowed mutably{}{}",
+                self.name(),
+                Self::holder(borrow),
+                Self::requester()
+            );
+        }
+        borrow.set_holder(RunningSystem::current());
         unsafe { self.cell.as_ref() }
     }
 
     #[allow(clippy::mut_from_ref)]
     pub fn borrow_mut(&self) -> &mut R0 {
-        assert!(
-            unsafe { self.borrow.as_ref().borrow_mut() },
-            "cannot borrow {} mutably: already borrowed",
-            std::any::type_name::<R0>()
-        );
-        unsafe { &mut *self.cell.clone().as_ptr() }
+        let borrow = unsafe { self.borrow.as_ref() };
+        if !borrow.borrow_mut() {
+            panic!(
+                "cannot borrow {} mutably: already borrowed{}{}",
+                self.name(),
+                Self::holder(borrow),
+                Self::requester()
+            );
+        }
+        borrow.set_holder(RunningSystem::current());
+        unsafe { &mut *self.cell.as_ptr() }
     }
 
     pub unsafe fn release(&self) {
@@ -55,7 +100,7 @@ impl<R0> Drop for ResourceCell<R0> {
             assert!(
                 unsafe { self.borrow.as_ref().is_free() },
                 "borrows of {} were not released properly",
-                std::any::type_name::<R0>()
+                self.name()
             )
         }
     }
@@ -64,3 +109,42 @@ impl<R0> Drop for ResourceCell<R0> {
 unsafe impl<R0> Send for ResourceCell<R0> where R0: Send {}
 
 unsafe impl<R0> Sync for ResourceCell<R0> where R0: Sync {}
+
+#[cfg(test)]
+mod tests {
+    use super::{AtomicBorrow, ResourceCell};
+    use crate::system_context::RunningSystem;
+    use std::ptr::NonNull;
+
+    #[test]
+    #[should_panic(
+        expected = "cannot borrow usize immutably: already borrowed mutably by system holder, \
+        in system requester"
+    )]
+    fn holder_mutable() {
+        let mut resource = 0usize;
+        let mut borrow = AtomicBorrow::new();
+        let cell = ResourceCell::new(NonNull::from(&mut resource), &mut borrow);
+        let holder = RunningSystem::enter("holder");
+        let _ = cell.borrow_mut();
+        drop(holder);
+        let _requester = RunningSystem::enter("requester");
+        let _ = cell.borrow();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "cannot borrow usize mutably: already borrowed by system holder, \
+        in system requester"
+    )]
+    fn holder_immutable() {
+        let mut resource = 0usize;
+        let mut borrow = AtomicBorrow::new();
+        let cell = ResourceCell::new(NonNull::from(&mut resource), &mut borrow);
+        let holder = RunningSystem::enter("holder");
+        let _ = cell.borrow();
+        drop(holder);
+        let _requester = RunningSystem::enter("requester");
+        let _ = cell.borrow_mut();
+    }
+}
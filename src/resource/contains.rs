@@ -1,7 +1,7 @@
-#[cfg(feature = "parallel")]
-use fixedbitset::FixedBitSet;
+use std::any::TypeId;
 
 use super::ResourceCell;
+use crate::ResourceKey;
 
 /// Specifies how a specific type may be borrowed from a tuple of cells.
 pub trait Contains<R0, M0> {
@@ -14,8 +14,8 @@ pub trait Contains<R0, M0> {
 
     unsafe fn release_mut(&self);
 
-    #[cfg(feature = "parallel")]
-    fn set_resource_bit(bitset: &mut FixedBitSet);
+    /// Returns the key borrows of the resource are recorded under in resource sets.
+    fn resource_key() -> ResourceKey;
 }
 
 impl<R0> Contains<R0, ()> for (ResourceCell<R0>,) {
@@ -35,9 +35,8 @@ impl<R0> Contains<R0, ()> for (ResourceCell<R0>,) {
         self.0.release_mut();
     }
 
-    #[cfg(feature = "parallel")]
-    fn set_resource_bit(bitset: &mut FixedBitSet) {
-        bitset.insert(0);
+    fn resource_key() -> ResourceKey {
+        ResourceKey::Index(0)
     }
 }
 
@@ -83,9 +82,8 @@ macro_rules! impl_contains {
                 $letter.release_mut();
             }
 
-            #[cfg(feature = "parallel")]
-            fn set_resource_bit(bitset: &mut FixedBitSet) {
-                bitset.insert(count!($($all)*) - (1usize + count!($($tail)*)));
+            fn resource_key() -> ResourceKey {
+                ResourceKey::Index(count!($($all)*) - (1usize + count!($($tail)*)))
             }
         }
         impl_contains!($($all),* ; $($tail),*);
@@ -116,12 +114,77 @@ macro_rules! impl_contains {
                 $letter.release_mut();
             }
 
-            #[cfg(feature = "parallel")]
-            fn set_resource_bit(bitset: &mut FixedBitSet) {
-                bitset.insert(count!($($all)*) - 1usize);
+            fn resource_key() -> ResourceKey {
+                ResourceKey::Index(count!($($all)*) - 1usize)
             }
         }
     }
 }
 
 impl_for_tuples!(impl_contains);
+
+/// Specifies how a type that may or may not be in a tuple of cells is looked up in it.
+pub trait MaybeContains {
+    fn cell<R0>(&self) -> Option<&ResourceCell<R0>>
+    where
+        R0: 'static;
+
+    fn key_of<R0>() -> Option<ResourceKey>
+    where
+        R0: 'static;
+}
+
+impl MaybeContains for () {
+    fn cell<R0>(&self) -> Option<&ResourceCell<R0>>
+    where
+        R0: 'static,
+    {
+        None
+    }
+
+    fn key_of<R0>() -> Option<ResourceKey>
+    where
+        R0: 'static,
+    {
+        None
+    }
+}
+
+macro_rules! impl_maybe_contains {
+    ($($letter:ident),*) => {
+        impl<$($letter),*> MaybeContains for ($(ResourceCell<$letter>,)*)
+        where
+            $($letter: 'static,)*
+        {
+            #[allow(non_snake_case)]
+            fn cell<R0>(&self) -> Option<&ResourceCell<R0>>
+            where
+                R0: 'static,
+            {
+                let ($($letter,)*) = self;
+                $(
+                    if TypeId::of::<R0>() == TypeId::of::<$letter>() {
+                        // Type IDs match, so `$letter` and `R0` are the same type.
+                        return Some(unsafe {
+                            &*($letter as *const ResourceCell<$letter> as *const ResourceCell<R0>)
+                        });
+                    }
+                )*
+                None
+            }
+
+            fn key_of<R0>() -> Option<ResourceKey>
+            where
+                R0: 'static,
+            {
+                [$(TypeId::of::<$letter>(),)*]
+                    .iter()
+                    .position(|type_id| *type_id == TypeId::of::<R0>())
+                    .map(ResourceKey::Index)
+            }
+        }
+    }
+}
+
+impl_maybe_contains!(A);
+impl_for_tuples!(impl_maybe_contains);
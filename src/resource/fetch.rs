@@ -1,16 +1,30 @@
-use super::Contains;
+use hecs::World;
+use std::marker::PhantomData;
 
-#[cfg(feature = "parallel")]
-use crate::BorrowSet;
+use super::{Contains, MaybeContains};
+use crate::{query_bundle::QueryExt, ArchetypeSet, BorrowSet, BorrowTypeSet, QueryMarker};
 
 /// Specifies how a tuple of types may be borrowed from a tuple of cells.
 pub trait Fetch<'a, T, M0>: Sized {
+    /// Borrows the types from the cells.
     fn fetch(resources: &'a T) -> Self;
 
+    /// Releases the borrows made by `fetch()`.
+    ///
+    /// # Safety
+    /// Must only be called once per `fetch()`, after the fetched value is no longer used.
     unsafe fn release(resources: &'a T);
 
-    #[cfg(feature = "parallel")]
+    /// Records which of the cells are borrowed, and how.
     fn set_resource_bits(resource_set: &mut BorrowSet);
+
+    /// Records component types of queries carried alongside the resources;
+    /// only overridden by types deriving `SystemParams`.
+    fn insert_component_types(_component_type_set: &mut BorrowTypeSet) {}
+
+    /// Records archetypes accessed by queries carried alongside the resources;
+    /// only overridden by types deriving `SystemParams`.
+    fn set_archetype_bits(_world: &World, _archetype_set: &mut ArchetypeSet) {}
 }
 
 impl<'a, T, M0, R0> Fetch<'a, T, M0> for &'a R0
@@ -26,9 +40,8 @@ where
         T::release(resources);
     }
 
-    #[cfg(feature = "parallel")]
     fn set_resource_bits(resource_set: &mut BorrowSet) {
-        T::set_resource_bit(&mut resource_set.immutable);
+        resource_set.insert_immutable::<R0>(T::resource_key());
     }
 }
 
@@ -45,9 +58,52 @@ where
         T::release_mut(resources);
     }
 
-    #[cfg(feature = "parallel")]
     fn set_resource_bits(resource_set: &mut BorrowSet) {
-        T::set_resource_bit(&mut resource_set.mutable);
+        resource_set.insert_mutable::<R0>(T::resource_key());
+    }
+}
+
+impl<'a, T, R0> Fetch<'a, T, ()> for Option<&'a R0>
+where
+    T: MaybeContains,
+    R0: 'static,
+{
+    fn fetch(resources: &'a T) -> Self {
+        resources.cell::<R0>().map(|cell| cell.borrow())
+    }
+
+    unsafe fn release(resources: &'a T) {
+        if let Some(cell) = resources.cell::<R0>() {
+            cell.release();
+        }
+    }
+
+    fn set_resource_bits(resource_set: &mut BorrowSet) {
+        if let Some(key) = T::key_of::<R0>() {
+            resource_set.insert_immutable::<R0>(key);
+        }
+    }
+}
+
+impl<'a, T, R0> Fetch<'a, T, ()> for Option<&'a mut R0>
+where
+    T: MaybeContains,
+    R0: 'static,
+{
+    fn fetch(resources: &'a T) -> Self {
+        resources.cell::<R0>().map(|cell| cell.borrow_mut())
+    }
+
+    unsafe fn release(resources: &'a T) {
+        if let Some(cell) = resources.cell::<R0>() {
+            cell.release_mut();
+        }
+    }
+
+    fn set_resource_bits(resource_set: &mut BorrowSet) {
+        if let Some(key) = T::key_of::<R0>() {
+            resource_set.insert_mutable::<R0>(key);
+        }
     }
 }
 
@@ -56,7 +112,6 @@ impl<'a, T> Fetch<'a, T, ()> for () {
 
     unsafe fn release(_: &'a T) {}
 
-    #[cfg(feature = "parallel")]
     fn set_resource_bits(_: &mut BorrowSet) {}
 }
 
@@ -72,10 +127,17 @@ where
         F0::release(resources);
     }
 
-    #[cfg(feature = "parallel")]
     fn set_resource_bits(resource_set: &mut BorrowSet) {
         F0::set_resource_bits(resource_set);
     }
+
+    fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
+        F0::insert_component_types(component_type_set);
+    }
+
+    fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
+        F0::set_archetype_bits(world, archetype_set);
+    }
 }
 
 macro_rules! impl_fetch {
@@ -95,13 +157,90 @@ macro_rules! impl_fetch {
                     $([<F $letter>]::release(resources);)*
                 }
 
-                #[cfg(feature = "parallel")]
                 fn set_resource_bits(resource_set: &mut BorrowSet) {
                     $([<F $letter>]::set_resource_bits(resource_set);)*
                 }
+
+                fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
+                    $([<F $letter>]::insert_component_types(component_type_set);)*
+                }
+
+                fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
+                    $([<F $letter>]::set_archetype_bits(world, archetype_set);)*
+                }
             }
         }
     }
 }
 
 impl_for_tuples!(impl_fetch);
+
+/// Marker for the `FetchField` implementation of query markers.
+pub struct QueryField;
+
+/// Marker for the `FetchField` implementation of resources, wrapping the marker of `Fetch`.
+pub struct ResourceField<M0>(PhantomData<M0>);
+
+/// Specifies how a field of a struct deriving `SystemParams` is obtained: query markers
+/// are created, and all other fields are fetched as resources.
+pub trait FetchField<'a, T, M0>: Sized {
+    /// Borrows or creates the field.
+    fn fetch(resources: &'a T) -> Self;
+
+    /// Releases the borrows made by `fetch()`.
+    ///
+    /// # Safety
+    /// Must only be called once per `fetch()`, after the fetched value is no longer used.
+    unsafe fn release(_resources: &'a T) {}
+
+    /// Records which of the cells are borrowed, and how.
+    fn set_resource_bits(_resource_set: &mut BorrowSet) {}
+
+    /// Records component types the field may query.
+    fn insert_component_types(_component_type_set: &mut BorrowTypeSet) {}
+
+    /// Records archetypes the field may query.
+    fn set_archetype_bits(_world: &World, _archetype_set: &mut ArchetypeSet) {}
+}
+
+impl<'a, T, Q0> FetchField<'a, T, QueryField> for QueryMarker<Q0>
+where
+    Q0: QueryExt,
+{
+    fn fetch(_: &'a T) -> Self {
+        QueryMarker::new()
+    }
+
+    fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
+        Q0::insert_query_component_types(component_type_set);
+    }
+
+    fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
+        Q0::set_archetype_bits(world, archetype_set);
+    }
+}
+
+impl<'a, T, M0, F0> FetchField<'a, T, ResourceField<M0>> for F0
+where
+    F0: Fetch<'a, T, M0>,
+{
+    fn fetch(resources: &'a T) -> Self {
+        F0::fetch(resources)
+    }
+
+    unsafe fn release(resources: &'a T) {
+        F0::release(resources);
+    }
+
+    fn set_resource_bits(resource_set: &mut BorrowSet) {
+        F0::set_resource_bits(resource_set);
+    }
+
+    fn insert_component_types(component_type_set: &mut BorrowTypeSet) {
+        F0::insert_component_types(component_type_set);
+    }
+
+    fn set_archetype_bits(world: &World, archetype_set: &mut ArchetypeSet) {
+        F0::set_archetype_bits(world, archetype_set);
+    }
+}
@@ -0,0 +1,232 @@
+use hecs::World;
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    ptr::NonNull,
+};
+
+use super::{AtomicBorrow, Contains, MaybeContains, RefExtractor, ResourceCell, ResourceTuple};
+use crate::{Executor, ResourceKey};
+
+struct Entry {
+    resource: Box<dyn Any + Send + Sync>,
+    borrow: AtomicBorrow,
+}
+
+/// A container of resources keyed by their types, for use as the generic parameter
+/// of an [`Executor`](struct.Executor.html) with any number of resources.
+///
+/// Executors over a `ResourceMap` accept a mutable reference to one when ran. Systems fetch
+/// resources from it by type, the same way they do from a tuple; access sets of the systems are
+/// still calculated when the executor is built, from their signatures.
+///
+/// # Example
+/// ```rust
+/// # use yaks::{Executor, ResourceMap};
+/// # let world = hecs::World::new();
+/// let mut resources = ResourceMap::new();
+/// resources.insert(1u32);
+/// resources.insert(2u64);
+/// let mut executor = Executor::<ResourceMap>::builder()
+///     .system(|_context, (a, b): (&mut u32, &u64), _queries: ()| {
+///         *a += *b as u32;
+///     })
+///     .system(|_context, c: Option<&f32>, _queries: ()| {
+///         assert!(c.is_none());
+///     })
+///     .build();
+/// executor.run(&world, &mut resources);
+/// assert_eq!(*resources.get::<u32>().unwrap(), 3);
+/// ```
+///
+/// # Panics
+/// Running an executor will panic if a system requires a resource that isn't in the map;
+/// systems that request an `Option<&R>` or an `Option<&mut R>` will receive `None` instead.
+#[derive(Default)]
+pub struct ResourceMap {
+    resources: HashMap<TypeId, Entry>,
+}
+
+impl ResourceMap {
+    /// Creates an empty resource map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a resource into the map, returning the previous resource of that type, if any.
+    pub fn insert<R0>(&mut self, resource: R0) -> Option<R0>
+    where
+        R0: Send + Sync + 'static,
+    {
+        self.resources
+            .insert(
+                TypeId::of::<R0>(),
+                Entry {
+                    resource: Box::new(resource),
+                    borrow: AtomicBorrow::new(),
+                },
+            )
+            .map(|entry| *entry.resource.downcast().expect("type IDs should match"))
+    }
+
+    /// Removes the resource of given type from the map, returning it, if present.
+    pub fn remove<R0>(&mut self) -> Option<R0>
+    where
+        R0: Send + Sync + 'static,
+    {
+        self.resources
+            .remove(&TypeId::of::<R0>())
+            .map(|entry| *entry.resource.downcast().expect("type IDs should match"))
+    }
+
+    /// Returns `true` if the map contains a resource of given type.
+    pub fn contains<R0>(&self) -> bool
+    where
+        R0: Send + Sync + 'static,
+    {
+        self.resources.contains_key(&TypeId::of::<R0>())
+    }
+
+    /// Returns a reference to the resource of given type, if present.
+    pub fn get<R0>(&self) -> Option<&R0>
+    where
+        R0: Send + Sync + 'static,
+    {
+        self.resources
+            .get(&TypeId::of::<R0>())
+            .and_then(|entry| entry.resource.downcast_ref())
+    }
+
+    /// Returns a mutable reference to the resource of given type, if present.
+    pub fn get_mut<R0>(&mut self) -> Option<&mut R0>
+    where
+        R0: Send + Sync + 'static,
+    {
+        self.resources
+            .get_mut(&TypeId::of::<R0>())
+            .and_then(|entry| entry.resource.downcast_mut())
+    }
+
+    fn wrap(&mut self) -> ResourceMapCells {
+        ResourceMapCells {
+            cells: self
+                .resources
+                .iter_mut()
+                .map(|(type_id, entry)| {
+                    let pointer = NonNull::from(&mut *entry.resource).cast::<()>();
+                    (*type_id, ResourceCell::new(pointer, &mut entry.borrow))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Cells of all resources in a `ResourceMap`, with their types erased.
+pub struct ResourceMapCells {
+    cells: HashMap<TypeId, ResourceCell<()>>,
+}
+
+impl ResourceTuple for ResourceMap {
+    type Wrapped = ResourceMapCells;
+    type BorrowTuple = ();
+    const LENGTH: usize = 0;
+
+    fn instantiate_borrows() -> Self::BorrowTuple {}
+}
+
+impl RefExtractor<&mut ResourceMap> for ResourceMap {
+    fn extract_and_run(executor: &mut Executor<Self>, world: &World, resources: &mut ResourceMap) {
+        let wrapped = resources.wrap();
+        executor.inner.run(world, wrapped);
+    }
+}
+
+impl ResourceMapCells {
+    fn expect_cell<R0>(&self) -> &ResourceCell<R0>
+    where
+        R0: 'static,
+    {
+        self.cell::<R0>().unwrap_or_else(|| {
+            panic!(
+                "cannot borrow {}: no such resource in the resource map",
+                type_name::<R0>()
+            )
+        })
+    }
+}
+
+impl<R0> Contains<R0, ()> for ResourceMapCells
+where
+    R0: 'static,
+{
+    fn borrow(&self) -> &R0 {
+        self.expect_cell::<R0>().borrow()
+    }
+
+    fn borrow_mut(&self) -> &mut R0 {
+        self.expect_cell::<R0>().borrow_mut()
+    }
+
+    unsafe fn release(&self) {
+        self.expect_cell::<R0>().release();
+    }
+
+    unsafe fn release_mut(&self) {
+        self.expect_cell::<R0>().release_mut();
+    }
+
+    fn resource_key() -> ResourceKey {
+        ResourceKey::Type(TypeId::of::<R0>())
+    }
+}
+
+impl MaybeContains for ResourceMapCells {
+    fn cell<R0>(&self) -> Option<&ResourceCell<R0>>
+    where
+        R0: 'static,
+    {
+        self.cells.get(&TypeId::of::<R0>()).map(|cell| {
+            // Type IDs match, so the erased cell points to a resource of type `R0`.
+            unsafe { &*(cell as *const ResourceCell<()> as *const ResourceCell<R0>) }
+        })
+    }
+
+    fn key_of<R0>() -> Option<ResourceKey>
+    where
+        R0: 'static,
+    {
+        Some(ResourceKey::Type(TypeId::of::<R0>()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResourceMap;
+    use crate::Executor;
+
+    struct A;
+    struct B;
+    struct C;
+
+    #[test]
+    fn resource_indices_per_executor() {
+        let executor = Executor::<ResourceMap>::builder()
+            .system(|_, _: (&mut A, &B), _: ()| {})
+            .build();
+        let set = &executor.resource_set;
+        assert_eq!(set.immutable.count_ones(..), 1);
+        assert_eq!(set.mutable.count_ones(..), 1);
+        assert!(set
+            .immutable
+            .ones()
+            .chain(set.mutable.ones())
+            .all(|index| index < 2));
+        let executor = Executor::<ResourceMap>::builder()
+            .system(|_, _: Option<&mut C>, _: ()| {})
+            .build();
+        assert_eq!(
+            executor.resource_set.mutable.ones().collect::<Vec<_>>(),
+            vec![0]
+        );
+    }
+}
@@ -1,30 +1,46 @@
 //! Resource flow:
-//! - resources argument is passed to `Executor::<Tuple: ResourceTuple>::run()`,
-//! - tuple of references to types in `Tuple` is extracted
-//! from the argument (`RefExtractor`),
+//! - resources argument is passed to `Executor::<Tuple: ResourceTuple>::run()`
+//!   (`ResourceMap` acts as a tuple of any length, with `ResourceMapCells` as the cells,
+//!   and a `ResourceBundle` acts as the tuple of its fields' types),
+//! - references to types in `Tuple` are extracted from the argument (`RefExtractor`),
+//!   picking each by type (`ResourceSource`); shared references are only accepted
+//!   for resources declared read-only in the builder, which no system may borrow mutably,
 //! - the references, together with `AtomicBorrow`s from the executor,
-//! are wrapped into `ResourceCell`s (`ResourceWrap`),
+//!   are wrapped into `ResourceCell`s (`ResourceWrap` for bundles),
 //! - when each system in the executor is ran, a subset tuple of references matching
-//! that of the system's resources argument is fetched from the cells, setting runtime
-//! borrow checking (`Fetch` for the whole tuple, `Contains` for each of it's elements),
+//!   that of the system's resources argument is fetched from the cells, setting runtime
+//!   borrow checking (`Fetch` for the whole tuple, `Contains` for each of it's elements,
+//!   or `MaybeContains` for optional elements),
 //! - the subset tuple of references is passed into the system's boxed closure,
 //! - after closure returns, the borrows are "released", resetting runtime
-//! borrow checking (`Fetch` and `Contains` again),
+//!   borrow checking (`Fetch` and `Contains` again),
 //! - after all of the systems have been ran, the cells are dropped.
 
 mod atomic_borrow;
+mod bundle;
 mod cell;
 mod contains;
 mod fetch;
+mod map;
+mod nested;
+mod owned;
 mod ref_extractor;
+mod source;
 mod tuple;
 mod wrap;
 
 use cell::ResourceCell;
-use contains::Contains;
+use contains::MaybeContains;
+
+pub(crate) use contains::Contains;
 
 pub use atomic_borrow::AtomicBorrow;
-pub use fetch::Fetch;
+pub use bundle::ResourceBundle;
+pub use fetch::{Fetch, FetchField};
+pub use map::ResourceMap;
+pub use nested::NestedResources;
+pub use owned::OwnedResources;
 pub use ref_extractor::RefExtractor;
+pub use source::ResourceSource;
 pub use tuple::ResourceTuple;
-pub use wrap::ResourceWrap;
+pub use wrap::{ResourceRef, ResourceWrap};
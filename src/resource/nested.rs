@@ -0,0 +1,110 @@
+use hecs::World;
+use std::ptr::NonNull;
+
+use super::{Contains, ResourceCell, ResourceTuple};
+use crate::{BorrowSet, Executor};
+
+/// Specifies how an executor over a tuple of resources is ran as a system of another executor,
+/// borrowing its resources from the cells of the outer executor.
+pub trait NestedResources<T, M0>: ResourceTuple + Sized {
+    /// Translates the resource set of the nested executor, with bits indexed by its own
+    /// resources, into the resource set of the outer executor.
+    fn set_resource_bits(nested: &BorrowSet, resource_set: &mut BorrowSet);
+
+    /// Borrows the resources the nested executor accesses from the cells,
+    /// runs it once, and releases the borrows.
+    fn run_nested(executor: &mut Executor<Self>, world: &World, resources: &T);
+}
+
+fn set_resource_bit<R0, M0, T>(nested: &BorrowSet, index: usize, resource_set: &mut BorrowSet)
+where
+    T: Contains<R0, M0>,
+{
+    if nested.mutable.contains(index) {
+        resource_set.insert_mutable::<R0>(T::resource_key());
+    } else if nested.immutable.contains(index) {
+        resource_set.insert_immutable::<R0>(T::resource_key());
+    }
+}
+
+fn borrow<R0, M0, T>(resources: &T, nested: &BorrowSet, index: usize) -> NonNull<R0>
+where
+    T: Contains<R0, M0>,
+{
+    if nested.mutable.contains(index) {
+        NonNull::from(<T as Contains<R0, M0>>::borrow_mut(resources))
+    } else if nested.immutable.contains(index) {
+        NonNull::from(<T as Contains<R0, M0>>::borrow(resources))
+    } else {
+        // No system of the nested executor accesses the resource, so the cell wrapping
+        // this pointer will never be borrowed from.
+        NonNull::dangling()
+    }
+}
+
+unsafe fn release<R0, M0, T>(resources: &T, nested: &BorrowSet, index: usize)
+where
+    T: Contains<R0, M0>,
+{
+    if nested.mutable.contains(index) {
+        <T as Contains<R0, M0>>::release_mut(resources);
+    } else if nested.immutable.contains(index) {
+        <T as Contains<R0, M0>>::release(resources);
+    }
+}
+
+impl<T> NestedResources<T, ()> for () {
+    fn set_resource_bits(_: &BorrowSet, _: &mut BorrowSet) {}
+
+    fn run_nested(executor: &mut Executor<Self>, world: &World, _: &T) {
+        executor.inner.run(world, ());
+    }
+}
+
+macro_rules! impl_nested_resources {
+    ($($letter:ident),*) => {
+        paste::item! {
+            impl<T, $($letter, [<M $letter>]),*> NestedResources<T, ($([<M $letter>],)*)>
+                for ($($letter,)*)
+            where
+                $($letter: Send + Sync,)*
+                $(T: Contains<$letter, [<M $letter>]>,)*
+            {
+                fn set_resource_bits(nested: &BorrowSet, resource_set: &mut BorrowSet) {
+                    let mut index = 0;
+                    $(
+                        set_resource_bit::<$letter, [<M $letter>], T>(
+                            nested,
+                            index,
+                            resource_set,
+                        );
+                        index += 1;
+                    )*
+                    let _ = index;
+                }
+
+                #[allow(non_snake_case)]
+                fn run_nested(executor: &mut Executor<Self>, world: &World, resources: &T) {
+                    let nested = &executor.resource_set;
+                    let mut index = 0;
+                    let ($([<P $letter>],)*) = ($({
+                        index += 1;
+                        borrow::<$letter, [<M $letter>], T>(resources, nested, index - 1)
+                    },)*);
+                    let ($($letter,)*) = &mut executor.borrows;
+                    let wrapped = ($(ResourceCell::new([<P $letter>], $letter),)*);
+                    executor.inner.run(world, wrapped);
+                    let mut index = 0;
+                    $(
+                        unsafe { release::<$letter, [<M $letter>], T>(resources, nested, index) };
+                        index += 1;
+                    )*
+                    let _ = index;
+                }
+            }
+        }
+    }
+}
+
+impl_nested_resources!(A);
+impl_for_tuples!(impl_nested_resources);
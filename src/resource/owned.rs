@@ -0,0 +1,79 @@
+use std::any::Any;
+
+use super::ResourceMap;
+
+/// Specifies how resources stored in an [`OwningExecutor`](struct.OwningExecutor.html)
+/// are accessed by type.
+pub trait OwnedResources {
+    /// Returns a reference to the resource of given type, if present.
+    fn get<R0>(&self) -> Option<&R0>
+    where
+        R0: Send + Sync + 'static;
+
+    /// Returns a mutable reference to the resource of given type, if present.
+    fn get_mut<R0>(&mut self) -> Option<&mut R0>
+    where
+        R0: Send + Sync + 'static;
+}
+
+impl OwnedResources for () {
+    fn get<R0>(&self) -> Option<&R0>
+    where
+        R0: Send + Sync + 'static,
+    {
+        None
+    }
+
+    fn get_mut<R0>(&mut self) -> Option<&mut R0>
+    where
+        R0: Send + Sync + 'static,
+    {
+        None
+    }
+}
+
+impl OwnedResources for ResourceMap {
+    fn get<R0>(&self) -> Option<&R0>
+    where
+        R0: Send + Sync + 'static,
+    {
+        ResourceMap::get(self)
+    }
+
+    fn get_mut<R0>(&mut self) -> Option<&mut R0>
+    where
+        R0: Send + Sync + 'static,
+    {
+        ResourceMap::get_mut(self)
+    }
+}
+
+macro_rules! impl_owned_resources {
+    ($($letter:ident),*) => {
+        impl<$($letter),*> OwnedResources for ($($letter,)*)
+        where
+            $($letter: 'static,)*
+        {
+            #[allow(non_snake_case)]
+            fn get<R0>(&self) -> Option<&R0>
+            where
+                R0: Send + Sync + 'static,
+            {
+                let ($($letter,)*) = self;
+                None$(.or_else(move || ($letter as &dyn Any).downcast_ref()))*
+            }
+
+            #[allow(non_snake_case)]
+            fn get_mut<R0>(&mut self) -> Option<&mut R0>
+            where
+                R0: Send + Sync + 'static,
+            {
+                let ($($letter,)*) = self;
+                None$(.or_else(move || ($letter as &mut dyn Any).downcast_mut()))*
+            }
+        }
+    }
+}
+
+impl_owned_resources!(A);
+impl_for_tuples!(impl_owned_resources);
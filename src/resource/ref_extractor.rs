@@ -1,13 +1,69 @@
 use hecs::World;
 
-use super::{ResourceTuple, ResourceWrap};
+use super::{source::pick_cell, ResourceSource, ResourceTuple};
 use crate::Executor;
 
-// TODO consider exposing.
-
-/// Specifies how a tuple of references may be extracted from the implementor and used
-/// as resources when running an executor.
-pub trait RefExtractor<RefSource>: ResourceTuple + Sized {
+/// Specifies how a tuple of references may be extracted from a resource source and used
+/// as resources when running an executor; implemented on the executor's generic parameter.
+///
+/// [`Executor::run()`](struct.Executor.html#method.run) accepts any `RefSource` that the
+/// executor's `Resources` implements this trait for. Out of the box, that's tuples of references
+/// (and a single mutable reference), a mutable reference to a tuple of the resources themselves,
+/// [`ResourceMap`](struct.ResourceMap.html) for executors over it, and, with
+/// the `resources-interop` feature, `resources::Resources`.
+///
+/// The `Marker` parameter is only used by the implementations for tuples, which pick
+/// the references out of the source by type, and is inferred.
+///
+/// Implementing this trait for your own container makes it usable as an argument of
+/// `Executor::run()`: extract the references from the container and pass them on
+/// to `Executor::run()` as a tuple.
+///
+/// # Safety contract
+/// The trait is safe to implement: the only way for an implementation to run the executor is
+/// `Executor::run()` itself, so the resources are still wrapped, borrow checked at runtime,
+/// and checked against the executor's systems the same way as when passing a tuple directly.
+/// Implementations must not hold on to the references after `extract_and_run()` returns,
+/// which the borrow checker enforces anyway, and must not run the executor more than once
+/// per call, since callers expect a single execution of each system.
+///
+/// # Example
+/// ```rust
+/// # use yaks::{Executor, RefExtractor};
+/// # use hecs::World;
+/// struct Time(f32);
+/// struct Gravity(f32);
+/// struct Speed(f32);
+///
+/// struct Container {
+///     time: Time,
+///     gravity: Gravity,
+///     speed: Speed,
+/// }
+///
+/// impl RefExtractor<&mut Container> for (Time, Speed) {
+///     fn extract_and_run(executor: &mut Executor<Self>, world: &World, source: &mut Container) {
+///         executor.run(world, (&source.time, &mut source.speed));
+///     }
+/// }
+///
+/// let world = World::new();
+/// let mut container = Container {
+///     time: Time(0.5),
+///     gravity: Gravity(9.8),
+///     speed: Speed(0.0),
+/// };
+/// let mut executor = Executor::<(Time, Speed)>::builder()
+///     .system(|_context, (time, speed): (&Time, &mut Speed), _queries: ()| {
+///         speed.0 += time.0;
+///     })
+///     .read_only::<Time, _>()
+///     .build();
+/// executor.run(&world, &mut container);
+/// assert_eq!(container.speed.0, 0.5);
+/// ```
+pub trait RefExtractor<RefSource, Marker = ()>: ResourceTuple + Sized {
+    /// Extracts references from `resources` and runs the executor with them once.
     fn extract_and_run(executor: &mut Executor<Self>, world: &World, resources: RefSource);
 }
 
@@ -17,42 +73,41 @@ impl RefExtractor<()> for () {
     }
 }
 
-impl<R0> RefExtractor<&mut R0> for (R0,)
-where
-    R0: Send + Sync,
-{
-    fn extract_and_run(executor: &mut Executor<Self>, world: &World, mut resources: &mut R0) {
-        let wrapped = resources.wrap(&mut executor.borrows);
-        executor.inner.run(world, wrapped);
-    }
-}
-
-impl<R0> RefExtractor<(&mut R0,)> for (R0,)
-where
-    R0: Send + Sync,
-{
-    fn extract_and_run(executor: &mut Executor<Self>, world: &World, mut resources: (&mut R0,)) {
-        let wrapped = resources.wrap(&mut executor.borrows);
-        executor.inner.run(world, wrapped);
+impl RefExtractor<&mut ()> for () {
+    fn extract_and_run(executor: &mut Executor<Self>, world: &World, _: &mut ()) {
+        executor.inner.run(world, ());
     }
 }
 
 macro_rules! impl_ref_extractor {
     ($($letter:ident),*) => {
-        impl<'a, $($letter),*> RefExtractor<($(&mut $letter,)*)> for ($($letter,)*)
-        where
-            $($letter: Send + Sync,)*
-        {
-            fn extract_and_run(
-                executor: &mut Executor<Self>,
-                world: &World,
-                mut resources: ($(&mut $letter,)*),
-            ) {
-                let wrapped = resources.wrap(&mut executor.borrows);
-                executor.inner.run(world, wrapped);
+        paste::item! {
+            impl<$($letter, [<M $letter>],)* S0> RefExtractor<S0, ($([<M $letter>],)*)>
+                for ($($letter,)*)
+            where
+                $($letter: Send + Sync,)*
+                $(S0: ResourceSource<$letter, [<M $letter>]>,)*
+            {
+                #[allow(non_snake_case)]
+                fn extract_and_run(executor: &mut Executor<Self>, world: &World, mut resources: S0) {
+                    let read_only = &executor.read_only;
+                    let ($($letter,)*) = &mut executor.borrows;
+                    let mut index = 0;
+                    let wrapped = ($({
+                        index += 1;
+                        pick_cell::<$letter, [<M $letter>], _>(
+                            &mut resources,
+                            $letter,
+                            index - 1,
+                            read_only,
+                        )
+                    },)*);
+                    executor.inner.run(world, wrapped);
+                }
             }
         }
     }
 }
 
+impl_ref_extractor!(A);
 impl_for_tuples!(impl_ref_extractor);
@@ -0,0 +1,105 @@
+use fixedbitset::FixedBitSet;
+use std::{any::type_name, ptr::NonNull};
+
+use super::{AtomicBorrow, ResourceCell, ResourceRef};
+
+/// Specifies how a resource of type `R0` is picked out of a resource source, such as a tuple
+/// of references, when running an executor; `M0` tells apart positions of the resource
+/// in the source, and is inferred.
+///
+/// This allows running an executor with a source that holds more resources than the executor
+/// requires, in any order; see [`Executor::run()`](struct.Executor.html#method.run). Out of the
+/// box, it's implemented for references, tuples of references, mutable references to tuples
+/// of resources, and mutable references to structs deriving `ResourceBundle`.
+///
+/// # Safety
+/// `pointer()` must return a pointer to a value of type `R0` that is valid for as long as
+/// the source is borrowed, and not aliased by pointers returned for other types or positions.
+/// If `MUTABLE` is `true`, the pointer must be valid for writes.
+pub unsafe trait ResourceSource<R0, M0> {
+    /// Whether or not the resource may be borrowed mutably through the pointer.
+    const MUTABLE: bool;
+
+    /// Returns the pointer to the resource.
+    fn pointer(&mut self) -> NonNull<R0>;
+}
+
+/// Picks the resource of type `R0` out of the source and wraps it into a cell,
+/// panicking if the source only holds a shared reference to a resource that
+/// is not in given set of resources declared read-only.
+pub(crate) fn pick_cell<R0, M0, S0>(
+    source: &mut S0,
+    borrow: &mut AtomicBorrow,
+    index: usize,
+    read_only: &FixedBitSet,
+) -> ResourceCell<R0>
+where
+    R0: Send + Sync,
+    S0: ResourceSource<R0, M0>,
+{
+    if !S0::MUTABLE && !read_only.contains(index) {
+        panic!(
+            "cannot run the executor with a shared reference to {}: \
+            it's not declared read-only",
+            type_name::<R0>()
+        );
+    }
+    ResourceCell::new(source.pointer(), borrow)
+}
+
+unsafe impl<R0> ResourceSource<R0, ()> for &'_ R0 {
+    const MUTABLE: bool = false;
+
+    fn pointer(&mut self) -> NonNull<R0> {
+        NonNull::from(*self)
+    }
+}
+
+unsafe impl<R0> ResourceSource<R0, ()> for &'_ mut R0 {
+    const MUTABLE: bool = true;
+
+    fn pointer(&mut self) -> NonNull<R0> {
+        NonNull::from(&mut **self)
+    }
+}
+
+macro_rules! impl_resource_source {
+    ($($letter:ident),*) => {
+        impl_resource_source!($($letter),* ; $($letter),*);
+    };
+    ($($all:ident),* ; $letter:ident $(, $tail:ident)*) => {
+        #[allow(non_snake_case)]
+        #[allow(unused_variables)]
+        unsafe impl<$($all),*> ResourceSource<$letter::Resource, [(); count!($($tail)*)]>
+            for ($($all,)*)
+        where
+            $($all: ResourceRef,)*
+        {
+            const MUTABLE: bool = $letter::MUTABLE;
+
+            fn pointer(&mut self) -> NonNull<$letter::Resource> {
+                let ($($all,)*) = self;
+                $letter.pointer()
+            }
+        }
+
+        #[allow(non_snake_case)]
+        #[allow(unused_variables)]
+        unsafe impl<$($all),*> ResourceSource<$letter, [(); count!($($tail)*)]>
+            for &'_ mut ($($all,)*)
+        {
+            const MUTABLE: bool = true;
+
+            fn pointer(&mut self) -> NonNull<$letter> {
+                let ($($all,)*) = &mut **self;
+                NonNull::from($letter)
+            }
+        }
+
+        impl_resource_source!($($all),* ; $($tail),*);
+    };
+    ($($all:ident),* ; ) => {};
+}
+
+impl_resource_source!(A);
+impl_for_tuples!(impl_resource_source);
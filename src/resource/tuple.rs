@@ -2,11 +2,18 @@ use super::{AtomicBorrow, ResourceCell};
 
 /// Specifies how a tuple behaves when used as the generic parameter of an executor.
 pub trait ResourceTuple {
+    /// Tuple of cells the resources are wrapped into when running an executor.
     type Wrapped: Send + Sync;
+    /// Tuple of `AtomicBorrow`s cached in an executor.
     type BorrowTuple: Send + Sync;
+    /// Amount of resource types.
     const LENGTH: usize;
 
+    /// Creates the `AtomicBorrow`s to cache in an executor.
     fn instantiate_borrows() -> Self::BorrowTuple;
+
+    /// Labels the cells, in order, for panic messages.
+    fn label_cells(_wrapped: &mut Self::Wrapped, _labels: &[&'static str]) {}
 }
 
 impl ResourceTuple for () {
@@ -28,6 +35,12 @@ where
     fn instantiate_borrows() -> Self::BorrowTuple {
         (AtomicBorrow::new(),)
     }
+
+    fn label_cells(wrapped: &mut Self::Wrapped, labels: &[&'static str]) {
+        if let Some(label) = labels.first() {
+            wrapped.0.set_label(label);
+        }
+    }
 }
 
 macro_rules! swap_to_atomic_borrow {
@@ -52,6 +65,17 @@ macro_rules! impl_resource_tuple {
             fn instantiate_borrows() -> Self::BorrowTuple {
                 ($(swap_to_atomic_borrow!(new $letter),)*)
             }
+
+            #[allow(non_snake_case)]
+            fn label_cells(wrapped: &mut Self::Wrapped, labels: &[&'static str]) {
+                let ($($letter,)*) = wrapped;
+                let mut labels = labels.iter();
+                $(
+                    if let Some(label) = labels.next() {
+                        $letter.set_label(label);
+                    }
+                )*
+            }
         }
     }
 }
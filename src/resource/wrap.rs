@@ -1,10 +1,48 @@
+use std::ptr::NonNull;
+
 use super::{AtomicBorrow, ResourceCell};
 
+/// Specifies how a reference to a resource is turned into a pointer for a cell.
+pub trait ResourceRef {
+    type Resource: Send + Sync;
+    /// Whether or not the resource may be borrowed mutably through the pointer.
+    const MUTABLE: bool;
+
+    fn pointer(&mut self) -> NonNull<Self::Resource>;
+}
+
+impl<R0> ResourceRef for &'_ R0
+where
+    R0: Send + Sync,
+{
+    type Resource = R0;
+    const MUTABLE: bool = false;
+
+    fn pointer(&mut self) -> NonNull<Self::Resource> {
+        NonNull::from(*self)
+    }
+}
+
+impl<R0> ResourceRef for &'_ mut R0
+where
+    R0: Send + Sync,
+{
+    type Resource = R0;
+    const MUTABLE: bool = true;
+
+    fn pointer(&mut self) -> NonNull<Self::Resource> {
+        NonNull::from(&mut **self)
+    }
+}
+
 /// Specifies how a tuple of references is wrapped into a tuple of cells.
 pub trait ResourceWrap {
+    /// Tuple of cells the references are wrapped into.
     type Wrapped: Send + Sync;
+    /// Tuple of `AtomicBorrow`s used by the cells.
     type BorrowTuple: Send + Sync;
 
+    /// Wraps the references into cells that use given `AtomicBorrow`s.
     fn wrap(&mut self, borrows: &mut Self::BorrowTuple) -> Self::Wrapped;
 }
 
@@ -23,19 +61,7 @@ where
     type BorrowTuple = (AtomicBorrow,);
 
     fn wrap(&mut self, borrows: &mut Self::BorrowTuple) -> Self::Wrapped {
-        (ResourceCell::new(self, &mut borrows.0),)
-    }
-}
-
-impl<R0> ResourceWrap for (&'_ mut R0,)
-where
-    R0: Send + Sync,
-{
-    type Wrapped = (ResourceCell<R0>,);
-    type BorrowTuple = (AtomicBorrow,);
-
-    fn wrap(&mut self, borrows: &mut Self::BorrowTuple) -> Self::Wrapped {
-        (ResourceCell::new(self.0, &mut borrows.0),)
+        (ResourceCell::new(self.pointer(), &mut borrows.0),)
     }
 }
 
@@ -51,22 +77,23 @@ macro_rules! swap_to_atomic_borrow {
 macro_rules! impl_resource_wrap {
     ($($letter:ident),*) => {
         paste::item! {
-            impl<$($letter),*> ResourceWrap for ($(&'_ mut $letter,)*)
+            impl<$([<R $letter>]),*> ResourceWrap for ($([<R $letter>],)*)
             where
-                $($letter: Send + Sync,)*
+                $([<R $letter>]: ResourceRef,)*
             {
-                type Wrapped = ($(ResourceCell<$letter>,)*);
+                type Wrapped = ($(ResourceCell<[<R $letter>]::Resource>,)*);
                 type BorrowTuple = ($(swap_to_atomic_borrow!($letter),)*);
 
                 #[allow(non_snake_case)]
                 fn wrap(&mut self, borrows: &mut Self::BorrowTuple) -> Self::Wrapped {
                     let ($([<S $letter>],)*) = self;
                     let ($([<B $letter>],)*) = borrows;
-                    ($( ResourceCell::new([<S $letter>], [<B $letter>]) ,)*)
+                    ($( ResourceCell::new([<S $letter>].pointer(), [<B $letter>]) ,)*)
                 }
             }
         }
     }
 }
 
+impl_resource_wrap!(A);
 impl_for_tuples!(impl_resource_wrap);
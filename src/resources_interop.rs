@@ -1,5 +1,5 @@
 use hecs::World;
-use resources::{Ref, RefMut, Resource, Resources};
+use resources::{CantGetResource, Ref, RefMut, Resource, Resources};
 
 use crate::{Executor, QueryBundle, RefExtractor, System, SystemContext};
 
@@ -88,6 +88,48 @@ where
     }
 }
 
+impl<'a, R0> Fetch<'a> for Option<&'_ R0>
+where
+    R0: Resource,
+{
+    type Wrapped = Option<Ref<'a, R0>>;
+
+    fn fetch(resources: &'a Resources) -> Self::Wrapped {
+        match resources.get() {
+            Ok(wrapped) => Some(wrapped),
+            Err(CantGetResource::NoSuchResource(_)) => None,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    fn deref(wrapped: &mut Self::Wrapped) -> Self {
+        wrapped
+            .as_ref()
+            .map(|wrapped| unsafe { std::mem::transmute(&**wrapped) })
+    }
+}
+
+impl<'a, R0> Fetch<'a> for Option<&'_ mut R0>
+where
+    R0: Resource,
+{
+    type Wrapped = Option<RefMut<'a, R0>>;
+
+    fn fetch(resources: &'a Resources) -> Self::Wrapped {
+        match resources.get_mut() {
+            Ok(wrapped) => Some(wrapped),
+            Err(CantGetResource::NoSuchResource(_)) => None,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    fn deref(wrapped: &mut Self::Wrapped) -> Self {
+        wrapped
+            .as_mut()
+            .map(|wrapped| unsafe { std::mem::transmute(&mut **wrapped) })
+    }
+}
+
 impl<'a, 'closure, Closure, Queries> System<'closure, (), Queries, &'a Resources, Resources>
     for Closure
 where
@@ -1,6 +1,7 @@
 use hecs::World;
+use std::any::type_name;
 
-use crate::{QueryBundle, SystemContext};
+use crate::{system_context::RunningSystem, QueryBundle, SystemContext};
 
 // TODO improve doc
 /// Automatically implemented on all closures and functions than
@@ -18,9 +19,12 @@ where
     Queries: QueryBundle,
 {
     fn run(&mut self, world: &World, resources: Resources) {
+        let name = type_name::<Closure>();
+        let _running = RunningSystem::enter(name);
         self(
             SystemContext {
                 system_id: None,
+                system_name: Some(name),
                 world,
             },
             resources,
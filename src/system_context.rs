@@ -1,6 +1,11 @@
 use hecs::{
     Archetype, ArchetypesGeneration, Entity, NoSuchEntity, Query, QueryBorrow, QueryOne, World,
 };
+use parking_lot::{const_mutex, Mutex};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
 
 use crate::{QueryMarker, SystemId};
 
@@ -11,6 +16,7 @@ use crate::{QueryMarker, SystemId};
 /// on how to call systems outside of an executor, as plain functions.
 pub struct SystemContext<'scope> {
     pub(crate) system_id: Option<SystemId>,
+    pub(crate) system_name: Option<&'static str>,
     pub(crate) world: &'scope World,
 }
 
@@ -22,6 +28,15 @@ impl<'scope> SystemContext<'scope> {
         self.system_id
     }
 
+    /// Returns the name of the system: the one given with
+    /// [`ExecutorBuilder::named()`](struct.ExecutorBuilder.html#method.named), or, by default,
+    /// the type name of the function or closure.
+    ///
+    /// Returns `None` only for contexts created by other means than running a system.
+    pub fn name(&self) -> Option<&'static str> {
+        self.system_name
+    }
+
     /// Prepares a query using the given [`QueryMarker`](struct.QueryMarker.html);
     /// see [`hecs::World::query()`](../hecs/struct.World.html#method.query).
     ///
@@ -125,3 +140,53 @@ impl<'scope> SystemContext<'scope> {
         self.world.archetypes_generation()
     }
 }
+
+thread_local! {
+    static RUNNING_SYSTEM: Cell<Option<&'static &'static str>> = const { Cell::new(None) };
+    static INTERNED_NAMES: RefCell<HashMap<&'static str, &'static &'static str>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Names interned by any thread; each distinct name is leaked once.
+static ALL_INTERNED_NAMES: Mutex<Vec<&'static &'static str>> = const_mutex(Vec::new());
+
+/// Returns a thin pointer that uniquely identifies the name, so that it can be stored
+/// in an `AtomicPtr`. Only the first lookup of a name on a given thread takes a lock.
+fn intern(name: &'static str) -> &'static &'static str {
+    INTERNED_NAMES.with(|interned| {
+        *interned.borrow_mut().entry(name).or_insert_with(|| {
+            let mut all = ALL_INTERNED_NAMES.lock();
+            match all.iter().find(|&&interned| *interned == name) {
+                Some(interned) => *interned,
+                None => {
+                    let interned: &'static &'static str = Box::leak(Box::new(name));
+                    all.push(interned);
+                    interned
+                }
+            }
+        })
+    })
+}
+
+/// Marks a system as running on the current thread, for as long as the guard is alive;
+/// used to name the system in panic messages of resource borrows.
+pub(crate) struct RunningSystem(Option<&'static &'static str>);
+
+impl RunningSystem {
+    pub fn enter(name: &'static str) -> Self {
+        let name = intern(name);
+        RunningSystem(RUNNING_SYSTEM.with(|running| running.replace(Some(name))))
+    }
+
+    /// Returns the name of the system running on the current thread, if any.
+    pub fn current() -> Option<&'static &'static str> {
+        RUNNING_SYSTEM.with(|running| running.get())
+    }
+}
+
+impl Drop for RunningSystem {
+    fn drop(&mut self) {
+        let previous = self.0;
+        RUNNING_SYSTEM.with(|running| running.set(previous));
+    }
+}
@@ -0,0 +1,97 @@
+use hecs::World;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use yaks::{Executor, QueryMarker};
+
+struct A(usize);
+
+struct B(usize);
+
+#[test]
+fn batch_with_init_reuses_value() {
+    let mut world = World::new();
+    world.spawn_batch((0..1000).map(|_| (A(0),)));
+    let inits = AtomicUsize::new(0);
+    yaks::batch_with_init(
+        &mut world.query::<&mut A>(),
+        10,
+        || {
+            inits.fetch_add(1, Ordering::Relaxed);
+            Vec::new()
+        },
+        |scratch: &mut Vec<usize>, _, a| {
+            scratch.push(1);
+            a.0 += 1;
+        },
+    );
+    for (_, a) in world.query::<&A>().iter() {
+        assert_eq!(a.0, 1);
+    }
+    let inits = inits.load(Ordering::Relaxed);
+    assert!(inits >= 1);
+    assert!(inits < 100);
+}
+
+#[test]
+fn try_batch_short_circuits() {
+    let mut world = World::new();
+    let invalid = world.spawn((A(0), B(0)));
+    world.spawn_batch((0..10000).map(|_| (A(0), B(1))));
+    let visited = AtomicUsize::new(0);
+    let result = yaks::try_batch(&mut world.query::<(&A, &B)>(), 10, |entity, (_, b)| {
+        visited.fetch_add(1, Ordering::Relaxed);
+        if b.0 == 0 {
+            Err(entity)
+        } else {
+            Ok(())
+        }
+    });
+    assert_eq!(result, Err(invalid));
+    assert!(visited.load(Ordering::Relaxed) < 10001);
+}
+
+#[test]
+fn batch_entities_subset() {
+    let mut world = World::new();
+    let entities: Vec<_> = world.spawn_batch((0..100).map(|_| (A(0), B(1)))).collect();
+    let selected: Vec<_> = entities.iter().copied().step_by(3).collect();
+    let mut executor = Executor::<(Vec<hecs::Entity>,)>::builder()
+        .system(
+            |context, selected: &Vec<hecs::Entity>, q: QueryMarker<(&mut A, &B)>| {
+                let missing = yaks::batch_entities(&context, q, selected, 4, |_, (a, b)| {
+                    a.0 += b.0;
+                });
+                assert!(missing.is_empty());
+            },
+        )
+        .build();
+    let mut selected_resource = selected.clone();
+    executor.run(&world, &mut selected_resource);
+    for (entity, a) in world.query::<&A>().iter() {
+        assert_eq!(a.0, if selected.contains(&entity) { 1 } else { 0 });
+    }
+}
+
+#[test]
+fn batch_entities_missing() {
+    let mut world = World::new();
+    let with_b = world.spawn((A(0), B(1)));
+    let without_b = world.spawn((A(0),));
+    let despawned = world.spawn((A(0), B(1)));
+    world.despawn(despawned).unwrap();
+    let selected = vec![despawned, with_b, without_b, with_b, despawned];
+    let mut executor = Executor::<(Vec<hecs::Entity>,)>::builder()
+        .system(
+            |context, selected: &Vec<hecs::Entity>, q: QueryMarker<(&mut A, &B)>| {
+                let missing = yaks::batch_entities(&context, q, selected, 1, |_, (a, b)| {
+                    a.0 += b.0;
+                });
+                assert_eq!(missing.len(), 2);
+                assert_eq!(missing[0], selected[0]);
+                assert_eq!(missing[1], selected[2]);
+            },
+        )
+        .build();
+    executor.run(&world, &mut selected.clone());
+    assert_eq!(world.get::<A>(with_b).unwrap().0, 1);
+    assert_eq!(world.get::<A>(without_b).unwrap().0, 0);
+}
@@ -1,9 +1,11 @@
-use yaks::{Executor, SystemContext};
+use yaks::{Executor, ExecutorBuilder, SystemContext};
 
 fn dummy_system(_: SystemContext, _: (), _: ()) {}
 
 #[test]
-#[should_panic(expected = "system 0 already exists")]
+#[should_panic(
+    expected = "system 0 already exists: builder::dummy_system cannot replace builder::dummy_system"
+)]
 fn duplicate_handle() {
     Executor::<()>::builder()
         .system_with_handle(dummy_system, 0)
@@ -21,7 +23,9 @@ fn duplicate_handle_with_deps() {
 }
 
 #[test]
-#[should_panic(expected = "could not resolve dependencies of system 1: no system 2 found")]
+#[should_panic(
+    expected = "could not resolve dependencies of system 1 (builder::dummy_system): no system 2 found"
+)]
 fn invalid_dependency() {
     Executor::<()>::builder()
         .system_with_handle(dummy_system, 0)
@@ -31,7 +35,8 @@ fn invalid_dependency() {
 
 #[test]
 #[should_panic(
-    expected = "could not resolve dependencies of a handle-less system: no system 1 found"
+    expected = "could not resolve dependencies of handle-less system builder::dummy_system: \
+    no system 1 found"
 )]
 fn invalid_dependency_no_handle() {
     Executor::<()>::builder()
@@ -41,10 +46,99 @@ fn invalid_dependency_no_handle() {
 }
 
 #[test]
-#[should_panic(expected = "system 1 depends on itself")]
+#[should_panic(expected = "system 1 (builder::dummy_system) depends on itself")]
 fn self_dependency() {
     Executor::<()>::builder()
         .system_with_handle(dummy_system, 0)
         .system_with_handle_and_deps(dummy_system, 1, vec![1])
         .build();
 }
+
+#[test]
+fn merge() {
+    let world = hecs::World::new();
+    let first = Executor::<(Vec<usize>,)>::builder()
+        .system_with_handle(|_, log: &mut Vec<usize>, _: ()| log.push(0), "first");
+    let second = Executor::<(Vec<usize>,)>::builder()
+        .system_with_handle(|_, log: &mut Vec<usize>, _: ()| log.push(1), "second")
+        .system_with_deps(|_, log: &mut Vec<usize>, _: ()| log.push(2), vec!["second"]);
+    let mut executor = Executor::<(Vec<usize>,)>::builder()
+        .system(|_, _: &Vec<usize>, _: ()| {})
+        .merge(first)
+        .merge(second)
+        .system_with_deps(
+            |_, log: &mut Vec<usize>, _: ()| {
+                assert!(log.contains(&0));
+                assert!(log.contains(&2));
+                log.push(3);
+            },
+            vec!["first", "second"],
+        )
+        .build();
+    let mut log = Vec::new();
+    executor.run(&world, &mut log);
+    assert_eq!(log.len(), 4);
+    assert!(log.iter().position(|i| *i == 1) < log.iter().position(|i| *i == 2));
+    assert_eq!(log[3], 3);
+}
+
+#[test]
+#[should_panic(expected = "cannot merge builders: system 0 exists in both of them, \
+    as builder::dummy_system and builder::dummy_system")]
+fn merge_duplicate_handle() {
+    Executor::<()>::builder()
+        .system_with_handle(dummy_system, 0)
+        .merge(Executor::<()>::builder().system_with_handle(dummy_system, 0))
+        .build();
+}
+
+#[test]
+fn named() {
+    let world = hecs::World::new();
+    let mut executor = Executor::<(Vec<&'static str>,)>::builder()
+        .system(|context, log: &mut Vec<&'static str>, _: ()| log.extend(context.name()))
+        .system_with_handle(
+            |context, log: &mut Vec<&'static str>, _: ()| log.extend(context.name()),
+            0,
+        )
+        .named("second")
+        .build();
+    assert!(executor.system_names()[0].starts_with("builder::named::"));
+    assert_eq!(executor.system_names()[1], "second");
+    let mut log = Vec::new();
+    executor.run(&world, &mut log);
+    log.sort_unstable();
+    assert!(log[0].starts_with("builder::named::"));
+    assert_eq!(log[1], "second");
+}
+
+#[test]
+#[should_panic(expected = "cannot name a system: the builder has no systems")]
+fn named_empty() {
+    Executor::<()>::builder().named("nothing").build();
+}
+
+#[test]
+#[should_panic(expected = "cannot limit the amount of concurrent systems to 0")]
+fn max_concurrent_systems_zero() {
+    Executor::<()>::builder()
+        .system(dummy_system)
+        .max_concurrent_systems(0)
+        .build();
+}
+
+fn doubling(builder: ExecutorBuilder<(usize,), i32>) -> ExecutorBuilder<(usize,), i32> {
+    builder.system_with_handle_and_deps(|_, value: &mut usize, _: ()| *value *= 2, 1, vec![0])
+}
+
+#[test]
+fn plugin() {
+    let world = hecs::World::new();
+    let mut executor = Executor::<(usize,)>::builder()
+        .system_with_handle(|_, value: &mut usize, _: ()| *value += 1, 0)
+        .plugin(doubling)
+        .build();
+    let mut value = 1;
+    executor.run(&world, &mut value);
+    assert_eq!(value, 4);
+}
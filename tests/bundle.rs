@@ -0,0 +1,86 @@
+#![cfg(feature = "macros")]
+
+use hecs::World;
+use yaks::{Executor, ResourceBundle};
+
+struct A(usize);
+
+struct B(usize);
+
+struct C(usize);
+
+#[derive(ResourceBundle)]
+struct Resources {
+    a: A,
+    b: B,
+    c: C,
+}
+
+#[derive(ResourceBundle)]
+struct Single {
+    a: A,
+}
+
+#[test]
+fn bundle_systems() {
+    let world = World::new();
+    let mut resources = Resources {
+        a: A(0),
+        b: B(1),
+        c: C(2),
+    };
+    let mut executor = Executor::<Resources>::builder()
+        .system(|_, (a, b): (&mut A, &B), _: ()| {
+            a.0 += b.0;
+        })
+        .system(|_, (b, c): (&B, &mut C), _: ()| {
+            c.0 += b.0;
+        })
+        .build();
+    executor.run(&world, &mut resources);
+    assert_eq!(resources.a.0, 1);
+    assert_eq!(resources.c.0, 3);
+    let mut single = Single { a: A(1) };
+    let mut executor = Executor::<Single>::builder()
+        .system(|_, (a, b): (&mut A, Option<&B>), _: ()| {
+            assert!(b.is_none());
+            a.0 += 1;
+        })
+        .build();
+    executor.run(&world, &mut single);
+    assert_eq!(single.a.0, 2);
+}
+
+#[test]
+#[should_panic(
+    expected = "borrows resource bundle::A mutably more than once, or both mutably and immutably"
+)]
+fn invalid_bundle_borrow() {
+    let world = World::new();
+    let mut resources = Resources {
+        a: A(0),
+        b: B(1),
+        c: C(2),
+    };
+    let mut executor = Executor::<Resources>::builder()
+        .system(|_, _: (&A, &mut A), _: ()| {})
+        .build();
+    executor.run(&world, &mut resources);
+}
+
+#[test]
+fn bundle_projection() {
+    let world = World::new();
+    let mut resources = Resources {
+        a: A(0),
+        b: B(1),
+        c: C(2),
+    };
+    let mut executor = Executor::<(C, A)>::builder()
+        .system(|_, (a, c): (&mut A, &C), _: ()| {
+            a.0 += c.0;
+        })
+        .build();
+    executor.run(&world, &mut resources);
+    assert_eq!(resources.a.0, 2);
+}
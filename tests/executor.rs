@@ -1,5 +1,5 @@
 use hecs::World;
-use yaks::{Executor, QueryMarker};
+use yaks::{Executor, QueryMarker, ResourceMap, SchedulingPolicy, SystemInfo};
 
 struct A(usize);
 
@@ -139,7 +139,39 @@ fn queries_decoding_four() {
 }
 
 #[test]
-#[should_panic(expected = "cannot borrow executor::A immutably: already borrowed mutably")]
+fn queries_several_archetypes() {
+    use std::{
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        time::Duration,
+    };
+    let mut world = World::new();
+    world.spawn((A(0),));
+    world.spawn((B(0),));
+    let running = AtomicUsize::new(0);
+    let overlapped = AtomicBool::new(false);
+    let track = || {
+        if running.fetch_add(1, Ordering::SeqCst) > 0 {
+            overlapped.store(true, Ordering::SeqCst);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        running.fetch_sub(1, Ordering::SeqCst);
+    };
+    // Both systems write to the archetype with `A`; the first one must not be considered
+    // to only access the archetypes of its last query.
+    let mut executor = Executor::<()>::builder()
+        .system(|_, _: (), _: (QueryMarker<&mut A>, QueryMarker<&B>)| track())
+        .system(|_, _: (), _: QueryMarker<&mut A>| track())
+        .build();
+    for _ in 0..5 {
+        executor.run(&world, ());
+    }
+    assert!(!overlapped.load(Ordering::SeqCst));
+}
+
+#[test]
+#[should_panic(
+    expected = "borrows resource executor::A mutably more than once, or both mutably and immutably"
+)]
 fn invalid_resources_mutable_immutable() {
     let world = World::new();
     let mut a = A(0);
@@ -152,7 +184,25 @@ fn invalid_resources_mutable_immutable() {
 }
 
 #[test]
-#[should_panic(expected = "cannot borrow executor::A mutably: already borrowed")]
+#[should_panic(
+    expected = "system executor::invalid_resources_named::{{closure}} borrows resource executor::A"
+)]
+fn invalid_resources_named() {
+    let world = World::new();
+    let mut a = A(0);
+    let mut b = B(1);
+    let mut c = C(2);
+    let mut executor = Executor::<(A, B, C)>::builder()
+        .system(|_, _: (&mut A, &A), _: ()| {})
+        .named("conflicting")
+        .build();
+    executor.run(&world, (&mut a, &mut b, &mut c));
+}
+
+#[test]
+#[should_panic(
+    expected = "borrows resource executor::A mutably more than once, or both mutably and immutably"
+)]
 fn invalid_resources_immutable_mutable() {
     let world = World::new();
     let mut a = A(0);
@@ -165,7 +215,9 @@ fn invalid_resources_immutable_mutable() {
 }
 
 #[test]
-#[should_panic(expected = "cannot borrow executor::A mutably: already borrowed")]
+#[should_panic(
+    expected = "borrows resource executor::A mutably more than once, or both mutably and immutably"
+)]
 fn invalid_resources_mutable_mutable() {
     let world = World::new();
     let mut a = A(0);
@@ -176,3 +228,544 @@ fn invalid_resources_mutable_mutable() {
         .build();
     executor.run(&world, (&mut a, &mut b, &mut c));
 }
+
+#[test]
+#[should_panic(
+    expected = "makes a query that borrows component executor::A mutably more than once, \
+    or both mutably and immutably"
+)]
+fn invalid_query_mutable_immutable() {
+    Executor::<()>::builder()
+        .system(|_, _: (), _: (QueryMarker<&B>, QueryMarker<(&mut A, &A)>)| {})
+        .build();
+}
+
+#[test]
+fn queries_mutable_immutable() {
+    let mut world = World::new();
+    world.spawn((A(1),));
+    let mut executor = Executor::<()>::builder()
+        .system(
+            |context, _: (), (q0, q1): (QueryMarker<&mut A>, QueryMarker<&A>)| {
+                for (_, a) in context.query(q0).iter() {
+                    a.0 += 1;
+                }
+                for (_, a) in context.query(q1).iter() {
+                    assert_eq!(a.0, 2);
+                }
+            },
+        )
+        .build();
+    executor.run(&world, ());
+}
+
+fn add_b_or_ten(_: yaks::SystemContext, (a, b, c): (&mut A, Option<&B>, Option<&C>), _: ()) {
+    assert!(c.is_none());
+    match b {
+        Some(b) => a.0 += b.0,
+        None => a.0 += 10,
+    }
+}
+
+#[test]
+fn resources_optional() {
+    let world = World::new();
+    let mut a = A(0);
+    let mut b = B(1);
+    let mut executor = Executor::<(A, B)>::builder()
+        .system_with_handle(
+            |_, b: Option<&mut B>, _: ()| {
+                b.expect("B should be present").0 += 1;
+            },
+            "increment",
+        )
+        .system_with_deps(add_b_or_ten, vec!["increment"])
+        .build();
+    executor.run(&world, (&mut a, &mut b));
+    assert_eq!(a.0, 2);
+    assert_eq!(b.0, 2);
+    let mut executor = Executor::<(A,)>::builder()
+        .system(add_b_or_ten)
+        .system(|_, b: Option<&mut B>, _: ()| assert!(b.is_none()))
+        .build();
+    executor.run(&world, &mut a);
+    assert_eq!(a.0, 12);
+}
+
+#[test]
+#[cfg(feature = "resources-interop")]
+fn resources_optional_interop() {
+    use yaks::System;
+    let world = World::new();
+    let mut resources = resources::Resources::new();
+    resources.insert(A(0));
+    add_b_or_ten.run(&world, &resources);
+    assert_eq!(resources.get::<A>().unwrap().0, 10);
+    resources.insert(B(1));
+    add_b_or_ten.run(&world, &resources);
+    assert_eq!(resources.get::<A>().unwrap().0, 11);
+    let mut executor = Executor::<(A,)>::builder()
+        .system(add_b_or_ten)
+        .system(|_, b: Option<&mut B>, _: ()| assert!(b.is_none()))
+        .build();
+    executor.run(&world, &resources);
+    assert_eq!(resources.get::<A>().unwrap().0, 21);
+}
+
+#[test]
+fn resources_shared() {
+    let world = World::new();
+    let mut a = A(0);
+    let b = B(1);
+    let c = C(2);
+    let mut executor = Executor::<(A, B, C)>::builder()
+        .system(|_, (a, b): (&mut A, &B), _: ()| {
+            a.0 += b.0;
+        })
+        .system(|_, (b, c): (&B, Option<&C>), _: ()| {
+            assert_eq!(b.0 + c.expect("C should be present").0, 3);
+        })
+        .read_only::<B, _>()
+        .read_only::<C, _>()
+        .build();
+    executor.run(&world, (&mut a, &b, &c));
+    assert_eq!(a.0, 1);
+    let mut executor = Executor::<(B,)>::builder()
+        .system(|_, b: &B, _: ()| assert_eq!(b.0, 1))
+        .read_only::<B, _>()
+        .build();
+    executor.run(&world, (&b,));
+}
+
+#[test]
+#[should_panic(
+    expected = "cannot build the executor: system mutate_a borrows resource executor::A \
+    mutably, but it's declared read-only"
+)]
+fn invalid_resources_shared_mutated() {
+    let _ = Executor::<(A, B)>::builder()
+        .system(|_, _: &B, _: ()| {})
+        .read_only::<A, _>()
+        .system(|_, _: Option<&mut A>, _: ()| {})
+        .named("mutate_a")
+        .build();
+}
+
+#[test]
+#[should_panic(
+    expected = "cannot run the executor with a shared reference to executor::A: \
+    it's not declared read-only"
+)]
+fn invalid_resources_shared_undeclared() {
+    let world = World::new();
+    let a = A(0);
+    let b = B(1);
+    let mut executor = Executor::<(A, B)>::builder()
+        .system(|_, _: (&A, &B), _: ()| {})
+        .read_only::<B, _>()
+        .build();
+    executor.run(&world, (&a, &b));
+}
+
+#[test]
+fn resources_map() {
+    let world = World::new();
+    let mut resources = ResourceMap::new();
+    resources.insert(A(0));
+    resources.insert(B(1));
+    let mut executor = Executor::<ResourceMap>::builder()
+        .system_with_handle(
+            |_, (a, b): (&mut A, &B), _: ()| {
+                a.0 += b.0;
+            },
+            "a",
+        )
+        .system_with_handle_and_deps(
+            |_, b: &mut B, _: ()| {
+                b.0 += 1;
+            },
+            "b",
+            vec!["a"],
+        )
+        .system_with_deps(add_b_or_ten, vec!["b"])
+        .build();
+    executor.run(&world, &mut resources);
+    assert_eq!(resources.get::<A>().unwrap().0, 3);
+    assert_eq!(resources.get::<B>().unwrap().0, 2);
+    resources.remove::<B>();
+    let mut executor = Executor::<ResourceMap>::builder()
+        .system(add_b_or_ten)
+        .system(|_, b: Option<&mut B>, _: ()| assert!(b.is_none()))
+        .build();
+    executor.run(&world, &mut resources);
+    assert_eq!(resources.get::<A>().unwrap().0, 13);
+}
+
+#[test]
+#[should_panic(expected = "cannot borrow executor::B: no such resource in the resource map")]
+fn invalid_resources_map_missing() {
+    let world = World::new();
+    let mut resources = ResourceMap::new();
+    resources.insert(A(0));
+    let mut executor = Executor::<ResourceMap>::builder()
+        .system(|_, _: (&A, &B), _: ()| {})
+        .build();
+    executor.run(&world, &mut resources);
+}
+
+fn count_entities(query: QueryMarker<&u32>, c: &mut C, context: yaks::SystemContext) {
+    c.0 += context.query(query).iter().count();
+}
+
+#[test]
+fn systems_flexible() {
+    let mut world = World::new();
+    world.spawn_batch((0..3).map(|_| (0u32,)));
+    let mut a = A(0);
+    let mut b = B(1);
+    let mut c = C(2);
+    let mut executor = Executor::<(A, B, C)>::builder()
+        .system(yaks::flexible(|| {}))
+        .system(yaks::flexible(|b: &B, a: &mut A| {
+            a.0 += b.0;
+        }))
+        .system(yaks::flexible(count_entities))
+        .system(yaks::flexible(
+            |_: &B, _: QueryMarker<&u32>, _: Option<&C>, (_, _): (&B, &B)| {},
+        ))
+        .build();
+    executor.run(&world, (&mut a, &mut b, &mut c));
+    assert_eq!(a.0, 1);
+    assert_eq!(c.0, 5);
+}
+
+#[test]
+#[should_panic(
+    expected = "borrows resource executor::A mutably more than once, or both mutably and immutably"
+)]
+fn invalid_flexible_mutable_mutable() {
+    let world = World::new();
+    let mut a = A(0);
+    let mut b = B(1);
+    let mut c = C(2);
+    let mut executor = Executor::<(A, B, C)>::builder()
+        .system(yaks::flexible(|_: &mut A, _: &mut A| {}))
+        .build();
+    executor.run(&world, (&mut a, &mut b, &mut c));
+}
+
+#[test]
+fn resources_owned() {
+    let world = World::new();
+    let mut executor = Executor::<(A, B, C)>::builder()
+        .system(|_, (a, b): (&mut A, &B), _: ()| {
+            a.0 += b.0;
+        })
+        .build()
+        .with_resources((A(0), B(1), C(2)));
+    executor.run(&world);
+    executor.run(&world);
+    assert_eq!(executor.resource::<A, _>().0, 2);
+    executor.resource_mut::<B, _>().0 = 3;
+    executor.run(&world);
+    let (mut executor, (mut a, mut b, mut c)) = executor.into_parts();
+    assert_eq!(a.0, 5);
+    executor.run(&world, (&mut a, &mut b, &mut c));
+    assert_eq!(a.0, 8);
+}
+
+#[test]
+#[should_panic(expected = "no resource of type executor::C in the executor")]
+fn invalid_resources_owned_missing() {
+    let mut resources = ResourceMap::new();
+    resources.insert(A(0));
+    let executor = Executor::<ResourceMap>::builder()
+        .build()
+        .with_resources(resources);
+    executor.resource::<C, _>();
+}
+
+#[test]
+fn resources_projection() {
+    let world = World::new();
+    let mut a = A(0);
+    let b = B(1);
+    let mut c = C(2);
+    let mut executor = Executor::<(A, B)>::builder()
+        .system(|_, (a, b): (&mut A, &B), _: ()| {
+            a.0 += b.0;
+        })
+        .read_only::<B, _>()
+        .build();
+    executor.run(&world, (&mut c, &mut a, &b));
+    assert_eq!(a.0, 1);
+    executor.run(&world, (&b, &mut a));
+    assert_eq!(a.0, 2);
+    let mut resources = (C(0), B(2), A(0));
+    executor.run(&world, &mut resources);
+    assert_eq!((resources.2).0, 2);
+    c.0 = 3;
+    let mut executor = Executor::<(C,)>::builder()
+        .system(|_, c: &mut C, _: ()| {
+            c.0 += 1;
+        })
+        .build();
+    executor.run(&world, &mut c);
+    assert_eq!(c.0, 4);
+}
+
+#[test]
+#[should_panic(
+    expected = "cannot run the executor with a shared reference to executor::A: \
+    it's not declared read-only"
+)]
+fn invalid_resources_projection_shared_undeclared() {
+    let world = World::new();
+    let a = A(0);
+    let b = B(1);
+    let mut c = C(2);
+    let mut executor = Executor::<(A, B)>::builder()
+        .system(|_, _: (&mut A, &B), _: ()| {})
+        .read_only::<B, _>()
+        .build();
+    executor.run(&world, (&b, &mut c, &a));
+}
+
+#[test]
+#[should_panic(
+    expected = "cannot borrow executor::A immutably: already borrowed mutably by system holder, \
+    in system requester"
+)]
+fn invalid_resources_borrow_held() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    let world = World::new();
+    let mut a = A(0);
+    let mut executor = Executor::<(A,)>::builder()
+        .system_with_handle(|_, _: &A, _: ()| {}, 0)
+        .named("requester")
+        .system_with_deps(
+            |_, a: &mut A, _: ()| {
+                // Unwinding out of the system leaves its borrow in place.
+                a.0 += 1;
+                panic!("holder failed");
+            },
+            vec![0],
+        )
+        .named("holder")
+        .build();
+    let failed = catch_unwind(AssertUnwindSafe(|| executor.run(&world, &mut a)));
+    assert!(failed.is_err());
+    assert_eq!(a.0, 1);
+    executor.run(&world, &mut a);
+}
+
+#[test]
+fn executor_nested() {
+    let mut world = World::new();
+    world.spawn_batch((0..10).map(|_| (A(0), B(0))));
+    let mut a = A(1);
+    let mut b = B(2);
+    let mut c = C(0);
+    let nested = Executor::<(A, C)>::builder()
+        .system(|ctx, (a, c): (&A, &mut C), q: QueryMarker<&mut A>| {
+            for (_, component) in ctx.query(q).iter() {
+                component.0 += a.0;
+            }
+            c.0 += 1;
+        })
+        .system(|ctx, a: &A, q: QueryMarker<&mut B>| {
+            for (_, component) in ctx.query(q).iter() {
+                component.0 += a.0;
+            }
+        })
+        .build();
+    let mut executor = Executor::<(A, B, C)>::builder()
+        .system_with_handle(
+            |_, (a, b): (&mut A, &B), _: ()| {
+                a.0 += b.0;
+            },
+            0,
+        )
+        .executor_with_handle_and_deps(nested, 1, vec![0])
+        .system_with_deps(
+            |ctx, c: &C, q: QueryMarker<(&A, &B)>| {
+                assert_eq!(c.0, 1);
+                for (_, (a, b)) in ctx.query(q).iter() {
+                    assert_eq!(a.0, b.0);
+                }
+            },
+            vec![1],
+        )
+        .build();
+    executor.run(&world, (&mut a, &mut b, &mut c));
+    assert_eq!(a.0, 3);
+    assert_eq!(c.0, 1);
+    for (_, (a, b)) in world.query::<(&A, &B)>().iter() {
+        assert_eq!(a.0, 3);
+        assert_eq!(b.0, 3);
+    }
+}
+
+#[test]
+fn systems_skipped_if_unmatched() {
+    let mut world = World::new();
+    world.spawn((A(0),));
+    let mut a = A(0);
+    let mut b = B(0);
+    let mut c = C(0);
+    let mut executor = Executor::<(A, B, C)>::builder()
+        .system_with_handle(|_, b: &mut B, _: QueryMarker<&C>| b.0 += 1, 0)
+        .skip_if_unmatched()
+        .system_with_deps(|_, c: &mut C, _: ()| c.0 += 1, vec![0])
+        .system(|_, a: &mut A, _: QueryMarker<&A>| a.0 += 1)
+        .skip_if_unmatched()
+        .build();
+    executor.run(&world, (&mut a, &mut b, &mut c));
+    assert_eq!((a.0, b.0, c.0), (1, 0, 1));
+    world.spawn((C(0),));
+    executor.run(&world, (&mut a, &mut b, &mut c));
+    assert_eq!((a.0, b.0, c.0), (2, 1, 2));
+}
+
+#[test]
+fn systems_skipped_if_unmatched_optional() {
+    let mut world = World::new();
+    let mut a = A(0);
+    let mut executor = Executor::<(A,)>::builder()
+        .system(|_, a: &mut A, _: QueryMarker<Option<&B>>| a.0 += 1)
+        .skip_if_unmatched()
+        .build();
+    executor.run(&world, &mut a);
+    assert_eq!(a.0, 0);
+    // The query matches the entity even though it has no `B`.
+    let entity = world.spawn((C(0),));
+    executor.run(&world, &mut a);
+    assert_eq!(a.0, 1);
+    // Archetypes stay the same, but are left without entities.
+    world.despawn(entity).unwrap();
+    executor.run(&world, &mut a);
+    assert_eq!(a.0, 1);
+    world.spawn((C(0),));
+    executor.run(&world, &mut a);
+    assert_eq!(a.0, 2);
+}
+
+struct ReverseInsertion;
+
+impl SchedulingPolicy for ReverseInsertion {
+    fn admit(&mut self, ready: &mut Vec<SystemInfo>) {
+        ready.sort_by_key(|system| std::cmp::Reverse(system.id()));
+    }
+}
+
+#[test]
+fn scheduling_policy() {
+    let world = World::new();
+    let mut order = Vec::new();
+    let mut executor = Executor::<(Vec<usize>,)>::builder()
+        .system(|_, order: &mut Vec<usize>, _: ()| order.push(0))
+        .system(|_, order: &mut Vec<usize>, _: ()| order.push(1))
+        .system(|_, order: &mut Vec<usize>, _: ()| order.push(2))
+        .scheduling_policy(ReverseInsertion)
+        .build();
+    executor.run(&world, &mut order);
+    if cfg!(feature = "parallel") {
+        assert_eq!(order, vec![2, 1, 0]);
+    } else {
+        assert_eq!(order, vec![0, 1, 2]);
+    }
+}
+
+#[cfg(feature = "parallel")]
+struct AdmitNone;
+
+#[cfg(feature = "parallel")]
+impl SchedulingPolicy for AdmitNone {
+    fn admit(&mut self, ready: &mut Vec<SystemInfo>) {
+        ready.clear();
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+#[should_panic(expected = "the scheduling policy admitted none of the ready systems")]
+fn invalid_scheduling_policy_admits_none() {
+    let world = World::new();
+    let mut executor = Executor::<(Vec<usize>,)>::builder()
+        .system(|_, _: &mut Vec<usize>, _: ()| {})
+        .system(|_, _: &mut Vec<usize>, _: ()| {})
+        .scheduling_policy(AdmitNone)
+        .build();
+    executor.run(&world, &mut Vec::new());
+}
+
+#[test]
+fn system_priority() {
+    let world = World::new();
+    let mut order = Vec::new();
+    let mut executor = Executor::<(Vec<usize>,)>::builder()
+        .system_with_handle(|_, order: &mut Vec<usize>, _: ()| order.push(0), 0)
+        .system_with_deps(|_, order: &mut Vec<usize>, _: ()| order.push(1), vec![0])
+        .system(|_, order: &mut Vec<usize>, _: ()| order.push(2))
+        .priority(1)
+        .build();
+    executor.run(&world, &mut order);
+    if cfg!(feature = "parallel") {
+        assert_eq!(order, vec![2, 0, 1]);
+    } else {
+        assert_eq!(order, vec![0, 1, 2]);
+    }
+}
+
+#[test]
+fn system_priority_disjoint() {
+    use std::sync::Mutex;
+    let world = World::new();
+    let order = &Mutex::new(Vec::new());
+    let mut executor = Executor::<()>::builder()
+        .system(move |_, _: (), _: ()| order.lock().unwrap().push(0))
+        .system(move |_, _: (), _: ()| order.lock().unwrap().push(1))
+        .priority(1)
+        .system(move |_, _: (), _: ()| order.lock().unwrap().push(2))
+        .priority(2)
+        .max_concurrent_systems(1)
+        .build();
+    executor.run(&world, ());
+    if cfg!(feature = "parallel") {
+        assert_eq!(*order.lock().unwrap(), vec![2, 1, 0]);
+    } else {
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+    }
+}
+
+#[test]
+fn max_concurrent_systems() {
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+    let world = World::new();
+    let (running, most_running) = (&AtomicUsize::new(0), &AtomicUsize::new(0));
+    let system = move |_: yaks::SystemContext, _: (), _: ()| {
+        let now_running = running.fetch_add(1, SeqCst) + 1;
+        most_running.fetch_max(now_running, SeqCst);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        running.fetch_sub(1, SeqCst);
+    };
+    let mut executor = Executor::<()>::builder()
+        .system(system)
+        .system(system)
+        .system(system)
+        .system(system)
+        .max_concurrent_systems(2)
+        .build();
+    executor.run(&world, ());
+    assert!(most_running.load(SeqCst) <= 2);
+    most_running.store(0, SeqCst);
+    let mut executor = Executor::<()>::builder()
+        .system_with_handle(system, 0)
+        .system(system)
+        .system(system)
+        .system_with_deps(system, vec![0])
+        .max_concurrent_systems(1)
+        .build();
+    executor.run(&world, ());
+    assert_eq!(most_running.load(SeqCst), 1);
+}
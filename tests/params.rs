@@ -0,0 +1,105 @@
+#![cfg(feature = "macros")]
+
+use hecs::{With, World};
+use yaks::{Executor, QueryMarker, SystemContext, SystemParams};
+
+struct A(usize);
+
+struct B(usize);
+
+struct C;
+
+struct Position(usize);
+
+struct Velocity(usize);
+
+#[derive(SystemParams)]
+struct Motion<'a> {
+    a: &'a A,
+    b: &'a mut B,
+    c: Option<&'a C>,
+    moving: QueryMarker<(&'a mut Position, &'a Velocity)>,
+    all: QueryMarker<&'a Position>,
+}
+
+#[derive(SystemParams)]
+struct Queries<'a> {
+    velocities: QueryMarker<With<Position, &'a mut Velocity>>,
+}
+
+fn motion(context: SystemContext, params: Motion, _: ()) {
+    assert!(params.c.is_none());
+    for (_, (position, velocity)) in context.query(params.moving).iter() {
+        position.0 += velocity.0 * params.a.0;
+    }
+    for (_, position) in context.query(params.all).iter() {
+        params.b.0 += position.0;
+    }
+}
+
+#[test]
+fn params_systems() {
+    let mut world = World::new();
+    world.spawn((Position(0), Velocity(1)));
+    world.spawn((Position(1),));
+    let mut a = A(1);
+    let mut b = B(0);
+    let mut executor = Executor::<(A, B)>::builder()
+        .system_with_handle(motion, 0)
+        .system_with_handle_and_deps(motion, 1, vec![0])
+        .system_with_deps(
+            |context, params: Queries, _: ()| {
+                for (_, velocity) in context.query(params.velocities).iter() {
+                    velocity.0 += 1;
+                }
+            },
+            vec![1],
+        )
+        .build();
+    executor.run(&world, (&mut a, &mut b));
+    let (position, velocity) = world
+        .query::<(&Position, &Velocity)>()
+        .iter()
+        .map(|(_, (position, velocity))| (position.0, velocity.0))
+        .next()
+        .unwrap();
+    assert_eq!(position, 2);
+    assert_eq!(velocity, 2);
+    assert_eq!(b.0, 5);
+}
+
+type Positions<'a> = QueryMarker<&'a mut Position>;
+
+mod user {
+    use super::B;
+    use yaks::SystemParams;
+
+    /// Not a query marker, despite the name.
+    #[derive(SystemParams)]
+    pub struct QueryMarker<'a> {
+        pub b: &'a mut B,
+    }
+}
+
+#[derive(SystemParams)]
+struct Aliased<'a> {
+    positions: Positions<'a>,
+    inner: user::QueryMarker<'a>,
+}
+
+#[test]
+fn params_query_alias() {
+    let mut world = World::new();
+    world.spawn((Position(1),));
+    let mut b = B(0);
+    let mut executor = Executor::<(B,)>::builder()
+        .system(|context, params: Aliased, _: ()| {
+            for (_, position) in context.query(params.positions).iter() {
+                position.0 += 1;
+                params.inner.b.0 += position.0;
+            }
+        })
+        .build();
+    executor.run(&world, &mut b);
+    assert_eq!(b.0, 2);
+}